@@ -1,9 +1,15 @@
 //! This module provides the `clap`-based `Args` struct. This is also used for invocations of
 //! sub-make using `$(MAKE)`.
 
-use clap::Parser;
+use std::env;
+
+use clap::{Parser, ValueEnum};
 use const_format::formatcp;
 
+use crate::error::log_warn;
+use crate::glob;
+use crate::paths;
+
 /// Represents the `clap`-based arguments provided by this binary.
 #[derive(Clone, Debug, Parser)]
 #[clap(
@@ -13,15 +19,27 @@ use const_format::formatcp;
     after_help = formatcp!(
         "License:  {}\nSource:   {}", env!("CARGO_PKG_LICENSE"), env!("CARGO_PKG_REPOSITORY")
     ),
+    args_override_self = true,
 )]
 pub struct Args {
-    /// Target(s) (if none specifired, use first regular target).
+    /// Target(s) (if none specifired, use first regular target), and variable overrides (e.g.,
+    /// `CC=clang`).
     #[arg()]
     pub targets: Vec<String>,
 
-    /// Read FILE as the makefile.
-    #[arg(short, long, visible_alias("makefile"))]
-    pub file: Option<String>,
+    /// Variable assignments from the command line (e.g., `CC=clang`), which take precedence over
+    /// assignments in the makefile. These are separated from `targets` by `split_overrides`.
+    #[arg(skip)]
+    pub overrides: Vec<String>,
+
+    /// Read FILE as a makefile (or `STDIN`, for `-`). Several makefiles are read in order.
+    #[arg(
+        short = 'f',
+        long = "file",
+        value_name = "FILE",
+        visible_alias("makefile")
+    )]
+    pub makefiles: Vec<String>,
 
     /// Ignored for compatibility.
     #[arg(short = 'b')]
@@ -39,10 +57,39 @@ pub struct Args {
     #[arg(short = 'C', long, value_name = "DIR")]
     pub directory: Vec<String>,
 
+    /// Search DIR for included makefiles which aren't found in the current directory. Several
+    /// directories are searched in order.
+    #[arg(short = 'I', long = "include-dir", value_name = "DIR")]
+    pub include_dirs: Vec<String>,
+
     /// Ignore errors from recipes.
     #[arg(short, long)]
     pub ignore_errors: bool,
 
+    /// Keep going when some targets can't be made.
+    #[arg(short, long)]
+    pub keep_going: bool,
+
+    /// Use the latest mtime between symlinks and their targets.
+    #[arg(short = 'L', long)]
+    pub check_symlink_times: bool,
+
+    /// Allow N jobs at once.
+    #[arg(short, long, value_name = "N", default_value_t = 1)]
+    pub jobs: usize,
+
+    /// Don't start new jobs while others are running and the load average is at least N. Without
+    /// N, there is no limit (e.g., to override a limit inherited through `MAKEFLAGS`).
+    #[arg(
+        short = 'l',
+        long,
+        value_name = "N",
+        visible_alias("load-average"),
+        num_args = 0..=1,
+        require_equals = true
+    )]
+    pub max_load: Option<f64>,
+
     /// Don't execute recipes; just print them.
     #[arg(
         short = 'n',
@@ -52,11 +99,79 @@ pub struct Args {
     )]
     pub just_print: bool,
 
-    /// Consider FILE to be very old and do not remake it.
+    /// Consider FILE to be very old and do not remake it. FILE may be a glob.
     #[arg(short, long, value_name = "FILE", visible_alias("assume-old"))]
     pub old_file: Vec<String>,
 
-    /// Consider FILE to be very new to simulate "what if" it changed.
+    /// Run no recipe; exit status says if up to date.
+    #[arg(short, long)]
+    pub question: bool,
+
+    /// Print the variables and rules after parsing the makefile, instead of making any targets.
+    #[arg(short, long)]
+    pub print_data_base: bool,
+
+    /// Print the dependency graph of the explicit rules in FORMAT after parsing the makefile,
+    /// instead of making any targets.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "dot"
+    )]
+    pub print_graph: Option<GraphFormat>,
+
+    /// Disable the builtin suffix rules.
+    #[arg(short = 'r', long)]
+    pub no_builtin_rules: bool,
+
+    /// Disable the builtin variables (such as `CC`). This implies `-r`.
+    #[arg(short = 'R', long)]
+    pub no_builtin_variables: bool,
+
+    /// Don't echo recipes.
+    #[arg(short, long, visible_alias("quiet"))]
+    pub silent: bool,
+
+    /// Print the working directory before and after making targets.
+    #[arg(short = 'w', long)]
+    pub print_directory: bool,
+
+    /// Don't print the working directory, even when it would be by default (see
+    /// `Args::prints_directory`).
+    #[arg(long, overrides_with = "print_directory")]
+    pub no_print_directory: bool,
+
+    /// Synchronize the output of parallel jobs by TYPE.
+    #[arg(
+        short = 'O',
+        long,
+        value_enum,
+        value_name = "TYPE",
+        default_value = "none",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "target"
+    )]
+    pub output_sync: OutputSync,
+
+    /// Give recipes STDIN by MODE. By default, parallel jobs read from the null device, so that
+    /// they don't compete for the terminal's input.
+    #[arg(long, value_enum, value_name = "MODE", default_value = "auto")]
+    pub stdin: StdinMode,
+
+    /// Prefix each line of recipe output with `[TARGET]`.
+    #[arg(long)]
+    pub prefix_output: bool,
+
+    /// Touch targets instead of remaking them.
+    #[arg(short, long)]
+    pub touch: bool,
+
+    /// Consider FILE to be very new to simulate "what if" it changed. FILE may be a glob, and `-o`
+    /// takes precedence.
     #[arg(
         short = 'W',
         long = "what-if",
@@ -66,7 +181,556 @@ pub struct Args {
     )]
     pub new_file: Vec<String>,
 
+    /// Don't remake targets whose newer prerequisites have the same contents as when the targets
+    /// were last made, according to the hashes recorded in `.omake-hashes`.
+    #[arg(long)]
+    pub check_hashes: bool,
+
+    /// Compare modification times with PRECISION.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "PRECISION",
+        default_value = "nanoseconds"
+    )]
+    pub mtime_precision: MtimePrecision,
+
+    /// Join the jobserver with the given descriptors (passed to sub-makes through `MAKEFLAGS`).
+    #[arg(long, value_name = "R,W", hide = true)]
+    pub jobserver_auth: Option<String>,
+
+    /// Print each recipe line with the target and the location of its rule.
+    #[arg(long)]
+    pub trace: bool,
+
+    /// Print how long the recipes of the slowest targets took to run, and how long the whole build
+    /// took. Sub-makes are timed as part of the recipes which invoke them.
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Warn whenever an undefined variable is expanded.
+    #[arg(long)]
+    pub warn_undefined_variables: bool,
+
+    /// Print debugging information about the FLAGS categories (basic if none are given).
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FLAGS",
+        value_delimiter = ',',
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "basic"
+    )]
+    pub debug: Vec<DebugFlag>,
+
+    /// Don't color log messages (which are otherwise colored when `STDERR` is a terminal, unless
+    /// `NO_COLOR` is set).
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Format log messages as TYPE.
+    #[arg(long, value_enum, value_name = "TYPE", default_value = "human")]
+    pub log_format: LogFormat,
+
     /// Print software license.
     #[arg(long)]
     pub license: bool,
 }
+
+/// The default arguments are those of a plain `make` invocation, without any flags or targets.
+impl Default for Args {
+    fn default() -> Self {
+        Self::parse_from(["make"])
+    }
+}
+
+impl Args {
+    /// Parse the command line, merged with any flags inherited through the `MAKEFLAGS` environment
+    /// variable. The inherited flags are placed before the command line arguments, so flags given
+    /// on the command line take precedence.
+    pub fn from_env() -> Self {
+        let argv = normalize_argv(env::args());
+        let makeflags = env::var("MAKEFLAGS").unwrap_or_default();
+        let flags = parse_makeflags(&makeflags);
+
+        if !flags.is_empty() {
+            let mut merged = vec![argv[0].clone()];
+            merged.extend(flags);
+            merged.extend(argv[1..].iter().cloned());
+
+            match Self::try_parse_from(normalize_argv(merged)) {
+                Ok(mut args) => {
+                    args.split_overrides();
+                    return args;
+                }
+                Err(e) => log_warn(
+                    format!("Ignoring invalid MAKEFLAGS `{makeflags}` ({}).", e.kind()),
+                    None,
+                ),
+            }
+        }
+
+        let mut args = Self::parse_from(argv);
+        args.split_overrides();
+        args
+    }
+
+    /// Check whether debugging information of the given category should be printed. Every other
+    /// category implies `basic`, and `all` implies every category.
+    pub fn debug(&self, flag: DebugFlag) -> bool {
+        match flag {
+            DebugFlag::Basic => !self.debug.is_empty(),
+            _ => self
+                .debug
+                .iter()
+                .any(|f| *f == flag || *f == DebugFlag::All),
+        }
+    }
+
+    /// Check whether `file` was given with `-o`, either exactly or by a glob. This takes precedence
+    /// over `is_new_file`.
+    pub fn is_old_file(&self, file: &str) -> bool {
+        self.old_file
+            .iter()
+            .any(|p| paths::same(p, file) || glob::matches_path(p, file))
+    }
+
+    /// Check whether `file` was given with `-W`, either exactly or by a glob.
+    pub fn is_new_file(&self, file: &str) -> bool {
+        self.new_file
+            .iter()
+            .any(|p| paths::same(p, file) || glob::matches_path(p, file))
+    }
+
+    /// Check whether to print the working directory before and after making targets. Like GNU
+    /// make, this is done with `-w`, and otherwise when changing directory with `-C` or in a
+    /// sub-make (i.e., at a `makelevel` above `0`), unless `-s` or `--no-print-directory` is given.
+    pub fn prints_directory(&self, makelevel: usize) -> bool {
+        if self.no_print_directory {
+            return false;
+        }
+
+        self.print_directory || (!self.silent && (!self.directory.is_empty() || makelevel > 0))
+    }
+
+    /// Move any variable assignments (i.e., arguments containing `=`) from `targets` to
+    /// `overrides`, preserving their order.
+    pub fn split_overrides(&mut self) {
+        let (overrides, targets) = self.targets.drain(..).partition(|t| t.contains('='));
+        self.targets = targets;
+        self.overrides.extend::<Vec<_>>(overrides);
+    }
+
+    /// Build the value of `MAKEFLAGS` for sub-makes. Like GNU make, single-letter flags are grouped
+    /// into the first word, and `-C`, `-f`, `-o`, and `-W` are not passed down.
+    pub fn makeflags(&self) -> String {
+        let mut letters = String::new();
+        for (flag, set) in [
+            ('B', self.always_make),
+            ('i', self.ignore_errors),
+            ('k', self.keep_going),
+            ('L', self.check_symlink_times),
+            ('n', self.just_print),
+            ('q', self.question),
+            ('r', self.no_builtin_rules),
+            ('R', self.no_builtin_variables),
+            ('s', self.silent),
+            ('t', self.touch),
+            ('w', self.print_directory),
+        ] {
+            if set {
+                letters.push(flag);
+            }
+        }
+
+        let mut words = vec![];
+        if !letters.is_empty() {
+            words.push(letters);
+        }
+        if self.jobs != 1 {
+            words.push(format!("-j{}", self.jobs));
+        }
+        if let Some(max_load) = self.max_load {
+            words.push(format!("-l{max_load}"));
+        }
+        for dir in &self.include_dirs {
+            words.push(format!("-I{}", escape(dir)));
+        }
+        if let Some(auth) = &self.jobserver_auth {
+            words.push(format!("--jobserver-auth={auth}"));
+        }
+        if self.no_print_directory {
+            words.push("--no-print-directory".to_string());
+        }
+        if self.output_sync == OutputSync::Target {
+            words.push("--output-sync=target".to_string());
+        }
+        match self.stdin {
+            StdinMode::Auto => (),
+            StdinMode::Inherit => words.push("--stdin=inherit".to_string()),
+            StdinMode::Null => words.push("--stdin=null".to_string()),
+        }
+        if self.mtime_precision == MtimePrecision::Seconds {
+            words.push("--mtime-precision=seconds".to_string());
+        }
+        if self.check_hashes {
+            words.push("--check-hashes".to_string());
+        }
+        if self.trace {
+            words.push("--trace".to_string());
+        }
+        if self.warn_undefined_variables {
+            words.push("--warn-undefined-variables".to_string());
+        }
+        if !self.debug.is_empty() {
+            let flags = self
+                .debug
+                .iter()
+                .filter_map(|f| f.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect::<Vec<_>>();
+            words.push(format!("--debug={}", flags.join(",")));
+        }
+        if self.no_color {
+            words.push("--no-color".to_string());
+        }
+        if self.log_format == LogFormat::Json {
+            words.push("--log-format=json".to_string());
+        }
+
+        // Variable overrides follow a `--` separator, with whitespace (and backslashes) escaped.
+        if !self.overrides.is_empty() {
+            words.push("--".to_string());
+            for assignment in &self.overrides {
+                words.push(escape(assignment));
+            }
+        }
+
+        words.join(" ")
+    }
+}
+
+/// Split a `MAKEFLAGS` value into arguments. The first word may be a group of single-letter flags
+/// without a leading `-`. Variable overrides (which may follow a `--` separator) are passed through
+/// as positional arguments, to be separated by `Args::split_overrides`.
+fn parse_makeflags(makeflags: &str) -> Vec<String> {
+    let mut flags = vec![];
+
+    for (i, word) in split_escaped(makeflags).into_iter().enumerate() {
+        if word == "--" {
+            continue;
+        } else if word.starts_with('-') || word.contains('=') || i != 0 {
+            flags.push(word);
+        } else {
+            flags.push(format!("-{word}"));
+        }
+    }
+
+    flags
+}
+
+/// Escape whitespace (and backslashes) in a word of `MAKEFLAGS` with backslashes, so that it is
+/// split back into the same word by `split_escaped`.
+fn escape(word: &str) -> String {
+    let mut escaped = String::new();
+    for ch in word.chars() {
+        if ch == '\\' || ch.is_whitespace() {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Split a string into words on whitespace, except where escaped with a backslash.
+fn split_escaped(s: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            ch if ch.is_whitespace() => words.extend(word.take()),
+            ch => word.get_or_insert_with(String::new).push(ch),
+        }
+    }
+    words.extend(word);
+
+    words
+}
+
+/// Represents the granularity of output synchronization for parallel jobs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputSync {
+    /// Output is not synchronized.
+    None,
+    /// Output of each target is grouped together.
+    Target,
+}
+
+/// Represents where recipes read their input from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum StdinMode {
+    /// Serial recipes inherit `STDIN`, but parallel jobs read from the null device.
+    Auto,
+    /// All recipes inherit `STDIN`, even parallel jobs.
+    Inherit,
+    /// All recipes read from the null device.
+    Null,
+}
+
+/// Represents the format in which the dependency graph is printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// The DOT language of Graphviz.
+    Dot,
+}
+
+/// Represents the precision with which modification times are compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MtimePrecision {
+    /// Whole seconds, like filesystems (and tools) with coarse timestamps.
+    Seconds,
+    /// The full precision provided by the filesystem (up to nanoseconds).
+    Nanoseconds,
+}
+
+/// Represents a category of debugging information.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DebugFlag {
+    /// Which targets are remade, and why.
+    Basic,
+    /// Every target considered, and how each prerequisite compares to it.
+    Verbose,
+    /// How pattern rules are chosen.
+    Implicit,
+    /// All of the above.
+    All,
+}
+
+/// Represents the format of log messages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable text.
+    #[default]
+    Human,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Rewrite the options whose values are optional into the `--name=value` form. `clap` can only make
+/// the value of an option optional by requiring it to follow an `=`, which is the usual convention
+/// for long options, but not for short ones (e.g., `-Otarget`). As in GNU make, `-l` also takes the
+/// next word as its value if it's a number, so `-l 2.5` sets a limit but `-l all` builds `all`.
+fn normalize_argv(argv: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut argv = argv.into_iter().peekable();
+    let mut normalized = vec![];
+
+    while let Some(arg) = argv.next() {
+        // Anything after `--` is a target, so leave it alone.
+        if arg == "--" {
+            normalized.push(arg);
+            normalized.extend(argv);
+            break;
+        }
+
+        if matches!(arg.as_str(), "-l" | "--max-load" | "--load-average") {
+            normalized.push(match argv.next_if(|word| is_number(word)) {
+                Some(value) => format!("--max-load={value}"),
+                None => "--max-load".to_string(),
+            });
+            continue;
+        }
+
+        normalized.push(match (arg.strip_prefix("-O"), arg.strip_prefix("-l")) {
+            (Some(""), _) => "--output-sync".to_string(),
+            (Some(value), _) => format!("--output-sync={value}"),
+            (_, Some(value)) => format!("--max-load={value}"),
+            _ => arg,
+        });
+    }
+
+    normalized
+}
+
+/// Check whether `word` is a number such as `2` or `2.5` (but not `inf`, which could be a target).
+fn is_number(word: &str) -> bool {
+    word.starts_with(|ch: char| ch.is_ascii_digit() || ch == '.') && word.parse::<f64>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(argv: &[&str]) -> Result<Args, clap::Error> {
+        Args::try_parse_from(normalize_argv(argv.iter().map(|a| a.to_string())))
+    }
+
+    #[test]
+    fn test_parse_makeflags() {
+        assert_eq!(parse_makeflags(""), Vec::<String>::new());
+        assert_eq!(parse_makeflags("Bi -j4"), vec!["-Bi", "-j4"]);
+        assert_eq!(
+            parse_makeflags(" -j4 --output-sync=target"),
+            vec!["-j4", "--output-sync=target"]
+        );
+        assert_eq!(parse_makeflags("n -- CC=gcc"), vec!["-n", "CC=gcc"]);
+        assert_eq!(parse_makeflags("CC=gcc -i"), vec!["CC=gcc", "-i"]);
+        assert_eq!(
+            parse_makeflags("-- CFLAGS=-O2\\ -g P=a\\\\b"),
+            vec!["CFLAGS=-O2 -g", "P=a\\b"]
+        );
+    }
+
+    #[test]
+    fn test_makeflags_round_trip() {
+        let args = parse(&[
+            "make",
+            "-B",
+            "-n",
+            "-s",
+            "-j",
+            "3",
+            "-l",
+            "2.5",
+            "-O",
+            "-I",
+            "my includes",
+            "--stdin=null",
+            "--mtime-precision=seconds",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.makeflags(),
+            "Bns -j3 -l2.5 -Imy\\ includes --output-sync=target --stdin=null \
+             --mtime-precision=seconds"
+        );
+
+        let inherited = Args::parse_from(normalize_argv(
+            ["make".to_string()]
+                .into_iter()
+                .chain(parse_makeflags(&args.makeflags())),
+        ));
+        assert!(inherited.always_make && inherited.just_print && inherited.silent);
+        assert_eq!(inherited.jobs, 3);
+        assert_eq!(inherited.max_load, Some(2.5));
+        assert_eq!(inherited.output_sync, OutputSync::Target);
+        assert_eq!(inherited.stdin, StdinMode::Null);
+        assert_eq!(inherited.include_dirs, ["my includes"]);
+        assert_eq!(inherited.mtime_precision, MtimePrecision::Seconds);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut args = Args::parse_from(["make", "all", "CC=clang", "CFLAGS=-O2 -g", "install"]);
+        args.split_overrides();
+        assert_eq!(args.targets, ["all", "install"]);
+        assert_eq!(args.overrides, ["CC=clang", "CFLAGS=-O2 -g"]);
+        assert_eq!(args.makeflags(), "-- CC=clang CFLAGS=-O2\\ -g");
+
+        let mut inherited = Args::parse_from(
+            ["make".to_string()]
+                .into_iter()
+                .chain(parse_makeflags(&args.makeflags())),
+        );
+        inherited.split_overrides();
+        assert!(inherited.targets.is_empty());
+        assert_eq!(inherited.overrides, args.overrides);
+    }
+
+    #[test]
+    fn test_debug() {
+        let args = Args::parse_from(["make"]);
+        assert!(!args.debug(DebugFlag::Basic));
+
+        let args = Args::parse_from(["make", "--debug"]);
+        assert!(args.debug(DebugFlag::Basic) && !args.debug(DebugFlag::Verbose));
+        assert_eq!(args.makeflags(), "--debug=basic");
+
+        let args = Args::parse_from(["make", "--debug=verbose,implicit"]);
+        assert!(args.debug(DebugFlag::Basic) && args.debug(DebugFlag::Implicit));
+        assert_eq!(args.makeflags(), "--debug=verbose,implicit");
+
+        let args = Args::parse_from(["make", "--debug=all"]);
+        assert!(args.debug(DebugFlag::Verbose) && args.debug(DebugFlag::Implicit));
+    }
+
+    #[test]
+    fn test_prints_directory() {
+        assert!(!Args::parse_from(["make"]).prints_directory(0));
+        assert!(Args::parse_from(["make"]).prints_directory(1));
+        assert!(Args::parse_from(["make", "-C", "dir"]).prints_directory(0));
+        assert!(Args::parse_from(["make", "-w"]).prints_directory(0));
+        assert!(!Args::parse_from(["make", "-s"]).prints_directory(1));
+        assert!(Args::parse_from(["make", "-s", "-w"]).prints_directory(1));
+        assert!(!Args::parse_from(["make", "-w", "--no-print-directory"]).prints_directory(1));
+        assert!(Args::parse_from(["make", "--no-print-directory", "-w"]).prints_directory(1));
+    }
+
+    #[test]
+    fn test_command_line_takes_precedence() {
+        let args = Args::parse_from(["make", "-j4", "-j2"]);
+        assert_eq!(args.jobs, 2);
+    }
+
+    #[test]
+    fn test_output_sync() {
+        assert_eq!(parse(&["make"]).unwrap().output_sync, OutputSync::None);
+        assert_eq!(
+            parse(&["make", "-O"]).unwrap().output_sync,
+            OutputSync::Target
+        );
+        assert_eq!(
+            parse(&["make", "-Onone"]).unwrap().output_sync,
+            OutputSync::None
+        );
+        assert_eq!(
+            parse(&["make", "--output-sync=none"]).unwrap().output_sync,
+            OutputSync::None
+        );
+
+        let args = parse(&["make", "-Otarget", "all"]).unwrap();
+        assert_eq!(args.output_sync, OutputSync::Target);
+        assert_eq!(args.targets, vec!["all"]);
+
+        // A separate word is never the value of `-O`.
+        let args = parse(&["make", "-O", "all"]).unwrap();
+        assert_eq!(args.output_sync, OutputSync::Target);
+        assert_eq!(args.targets, vec!["all"]);
+
+        // Nor is a short option attached to it.
+        assert!(parse(&["make", "-j2", "-Ot"]).is_err());
+        assert_eq!(
+            parse(&["make", "--", "-Onone"]).unwrap().targets,
+            vec!["-Onone"]
+        );
+    }
+
+    #[test]
+    fn test_max_load() {
+        assert_eq!(parse(&["make"]).unwrap().max_load, None);
+        assert_eq!(parse(&["make", "-l2.5"]).unwrap().max_load, Some(2.5));
+        assert_eq!(parse(&["make", "-l", "2.5"]).unwrap().max_load, Some(2.5));
+        assert_eq!(
+            parse(&["make", "--load-average", "3"]).unwrap().max_load,
+            Some(3.0)
+        );
+        assert_eq!(
+            parse(&["make", "--max-load=3"]).unwrap().max_load,
+            Some(3.0)
+        );
+
+        // A bare `-l` removes an earlier limit.
+        assert_eq!(parse(&["make", "-l2.5", "-l"]).unwrap().max_load, None);
+
+        // A word which isn't a number is a target, not the limit.
+        let args = parse(&["make", "-l", "all"]).unwrap();
+        assert_eq!(args.max_load, None);
+        assert_eq!(args.targets, vec!["all"]);
+        let args = parse(&["make", "-l", "inf"]).unwrap();
+        assert_eq!(args.max_load, None);
+        assert_eq!(args.targets, vec!["inf"]);
+    }
+}
@@ -16,6 +16,9 @@ pub struct Context {
     pub column_number: usize,
 
     pub line: Option<String>,
+
+    /// The target being made, if this context is part of making one.
+    pub target: Option<String>,
 }
 
 impl Context {
@@ -25,9 +28,16 @@ impl Context {
             line_number: 0,
             column_number: 0,
             line: None,
+            target: None,
         }
     }
 
+    /// Attach the target being made to this context.
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
     pub fn label(&self) -> Option<String> {
         self.path.as_ref().map(|path| {
             if self.line_number == 0 {
@@ -80,6 +90,7 @@ impl From<PathBuf> for Context {
             line_number: 0,
             column_number: 0,
             line: None,
+            target: None,
         }
     }
 }
@@ -1,20 +1,124 @@
+use std::env;
 use std::error::Error;
 use std::fmt;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 
+use crate::args::LogFormat;
 use crate::context::Context;
 
+const DEBUG: &str = "DEBUG";
 const INFO: &str = "INFO";
 const WARN: &str = "WARN";
 const ERROR: &str = "ERROR";
 const MAX_SEVERITY_LENGTH: usize = 5;
 
-/// Formatter for all log messages.
+/// ANSI escape sequences for the colors of human-readable log messages.
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether log messages are formatted as JSON, rather than human-readable text.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Whether human-readable log messages are colored.
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Set the format of all log messages for this process.
+pub fn set_log_format(format: LogFormat) {
+    JSON.store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+/// Set whether human-readable log messages are colored for this process. Even if `enabled`, they
+/// are only colored when `STDERR` is a terminal, and `NO_COLOR` is not set (see
+/// <https://no-color.org>).
+pub fn set_color(enabled: bool) {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    COLOR.store(
+        enabled && !no_color && io::stderr().is_terminal(),
+        Ordering::Relaxed,
+    );
+}
+
+/// Formatter for all log messages, in the configured format.
 fn format_log(msg: impl AsRef<str>, level: &str, context: Option<&Context>) -> String {
+    if JSON.load(Ordering::Relaxed) {
+        format_json(msg.as_ref(), level, context)
+    } else {
+        format_human(msg, level, context, COLOR.load(Ordering::Relaxed))
+    }
+}
+
+/// Wrap `text` in the given color, if `color` is enabled.
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("{code}{text}{RESET}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Format a log message as a single line of JSON, with the level, message, target, and location.
+/// Missing fields are `null`.
+fn format_json(msg: &str, level: &str, context: Option<&Context>) -> String {
+    let string = |s: Option<&str>| s.map_or("null".to_string(), json_string);
+    let path = context
+        .and_then(|c| c.path.as_ref())
+        .map(|p| p.display().to_string());
+    let line = context
+        .map(|c| c.line_number)
+        .filter(|n| *n != 0)
+        .map_or("null".to_string(), |n| n.to_string());
+
+    format!(
+        "{{\"level\":{},\"message\":{},\"target\":{},\"file\":{},\"line\":{}}}",
+        json_string(&level.to_lowercase()),
+        json_string(msg),
+        string(context.and_then(|c| c.target.as_deref())),
+        string(path.as_deref()),
+        line,
+    )
+}
+
+/// Quote and escape a string for JSON.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Formatter for human-readable log messages. With `color`, warnings are yellow, errors are red,
+/// and the context label is dim.
+fn format_human(
+    msg: impl AsRef<str>,
+    level: &str,
+    context: Option<&Context>,
+    color: bool,
+) -> String {
     // Format log level and context label/line.
     let level_display = format!("{:0width$}", level, width = MAX_SEVERITY_LENGTH);
+    let level_display = match level {
+        WARN => paint(&level_display, YELLOW, color),
+        ERROR => paint(&level_display, RED, color),
+        _ => level_display,
+    };
     let context_label = context
         .and_then(|c| c.label())
-        .map(|l| format!("[{}] ", l))
+        .map(|l| format!("{} ", paint(&format!("[{}]", l), DIM, color)))
         .unwrap_or_default();
 
     // Only show the context line if we are logging warnings or errors.
@@ -35,6 +139,11 @@ fn format_log(msg: impl AsRef<str>, level: &str, context: Option<&Context>) -> S
     )
 }
 
+/// Log a `DEBUG` message to `STDERR`.
+pub fn log_debug(msg: impl AsRef<str>, context: Option<&Context>) {
+    eprintln!("{}", format_log(msg, DEBUG, context));
+}
+
 /// Log an `INFO` message to `STDERR`.
 pub fn log_info(msg: impl AsRef<str>, context: Option<&Context>) {
     eprintln!("{}", format_log(msg, INFO, context));
@@ -50,6 +159,17 @@ pub fn log_error(msg: impl AsRef<str>, context: Option<&Context>) {
     eprintln!("{}", format_log(msg, ERROR, context));
 }
 
+/// Log an `INFO` message that we are entering `dir` (for `-w`). Messages about the directory use
+/// GNU make's wording and quoting, so editors can resolve relative paths in later messages.
+pub fn log_entering_directory(dir: &Path) {
+    log_info(format!("Entering directory '{}'", dir.display()), None);
+}
+
+/// Log an `INFO` message that we are leaving `dir` (for `-w`).
+pub fn log_leaving_directory(dir: &Path) {
+    log_info(format!("Leaving directory '{}'", dir.display()), None);
+}
+
 /// Represents a generic error in a makefile, including context.
 #[derive(Debug)]
 pub struct MakeError {
@@ -73,3 +193,50 @@ impl fmt::Display for MakeError {
         write!(f, "{}", format_log(&self.msg, ERROR, Some(&self.context)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(
+            json_string("a \"b\"\\c\n\u{1}"),
+            "\"a \\\"b\\\"\\\\c\\n\\u0001\""
+        );
+    }
+
+    #[test]
+    fn test_format_human() {
+        let mut context = Context::from(std::path::PathBuf::from("Makefile"));
+        context.line_number = 3;
+        assert_eq!(
+            format_human("Careful.", WARN, Some(&context), false),
+            "make: WARN  [Makefile] | Careful."
+        );
+        assert_eq!(
+            format_human("Careful.", WARN, Some(&context), true),
+            "make: \x1b[33mWARN \x1b[0m \x1b[2m[Makefile]\x1b[0m | Careful."
+        );
+        assert_eq!(
+            format_human("Failed.", ERROR, None, true),
+            "make: \x1b[31mERROR\x1b[0m | Failed."
+        );
+        assert_eq!(format_human("Hi.", INFO, None, true), "make: INFO  | Hi.");
+    }
+
+    #[test]
+    fn test_format_json() {
+        let mut context = Context::from(std::path::PathBuf::from("Makefile")).with_target("all");
+        context.line_number = 3;
+        assert_eq!(
+            format_json("Failed.", ERROR, Some(&context)),
+            r#"{"level":"error","message":"Failed.","target":"all","file":"Makefile","line":3}"#
+        );
+        assert_eq!(
+            format_json("Hi.", INFO, None),
+            r#"{"level":"info","message":"Hi.","target":null,"file":null,"line":null}"#
+        );
+    }
+}
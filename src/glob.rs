@@ -0,0 +1,170 @@
+//! A minimal implementation of shell-style globbing, supporting `*`, `?`, and bracket expressions
+//! (e.g., `[a-z]` or `[!0-9]`). This is all that is needed for functions like `$(wildcard ...)`,
+//! so we avoid pulling in an external crate.
+
+use std::fs;
+use std::path::Path;
+
+use crate::paths;
+
+const GLOB_CHARS: [char; 3] = ['*', '?', '['];
+
+/// Check if a string contains any glob metacharacters.
+pub fn is_glob(s: &str) -> bool {
+    s.contains(GLOB_CHARS)
+}
+
+/// Match a bracket expression (with the leading `[` already consumed) against `c`, returning
+/// whether it matched and the rest of the pattern, or `None` if the bracket is not terminated.
+fn match_bracket(pattern: &[char], c: char) -> Option<(bool, &[char])> {
+    let (negated, mut rest) = match pattern.first() {
+        Some('!' | '^') => (true, &pattern[1..]),
+        _ => (false, pattern),
+    };
+
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        match rest {
+            [] => return None,
+            [']', tail @ ..] if !first => return Some((matched != negated, tail)),
+            [lo, '-', hi, tail @ ..] if *hi != ']' => {
+                matched |= (*lo..=*hi).contains(&c);
+                rest = tail;
+            }
+            [ch, tail @ ..] => {
+                matched |= *ch == c;
+                rest = tail;
+            }
+        }
+        first = false;
+    }
+}
+
+fn matches_chars(pattern: &[char], s: &[char]) -> bool {
+    match pattern {
+        [] => s.is_empty(),
+        ['*', rest @ ..] => (0..=s.len()).any(|i| matches_chars(rest, &s[i..])),
+        ['?', rest @ ..] => !s.is_empty() && matches_chars(rest, &s[1..]),
+        ['[', rest @ ..] => {
+            let Some(&c) = s.first() else {
+                return false;
+            };
+
+            match match_bracket(rest, c) {
+                Some((matched, rest)) => matched && matches_chars(rest, &s[1..]),
+                // An unterminated bracket is matched literally.
+                None => c == '[' && matches_chars(rest, &s[1..]),
+            }
+        }
+        ['\\', ch, rest @ ..] | [ch, rest @ ..] => {
+            s.first() == Some(ch) && matches_chars(rest, &s[1..])
+        }
+    }
+}
+
+/// Check if `s` matches the glob `pattern` in its entirety. Note that `/` is not treated specially
+/// here, so this should be used on individual path components.
+pub fn matches(pattern: &str, s: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let s = s.chars().collect::<Vec<_>>();
+    matches_chars(&pattern, &s)
+}
+
+/// Check if `path` matches the glob `pattern`, component by component, so wildcards never match a
+/// `/`. As with `glob`, wildcards do not match a leading `.` in a file name unless the pattern
+/// component also starts with a `.`.
+pub fn matches_path(pattern: &str, path: &str) -> bool {
+    let patterns = paths::components(pattern).collect::<Vec<_>>();
+    let components = paths::components(path).collect::<Vec<_>>();
+    patterns.len() == components.len()
+        && patterns.iter().zip(components).all(|(pattern, component)| {
+            (!component.starts_with('.') || pattern.starts_with('.')) && matches(pattern, component)
+        })
+}
+
+/// Expand a glob pattern against the file system, returning the sorted list of matching paths.
+/// As with shells, wildcards do not match a leading `.` in a file name unless the pattern component
+/// also starts with a `.`.
+pub fn glob(pattern: &str) -> Vec<String> {
+    let mut paths = vec![String::new()];
+    let components = paths::components(pattern).collect::<Vec<_>>();
+
+    for (i, component) in components.iter().enumerate() {
+        let last = i == components.len() - 1;
+
+        paths = paths
+            .into_iter()
+            .flat_map(|prefix| {
+                // Plain components are just appended. Note that an empty first component comes
+                // from a leading `/`, so the path stays absolute.
+                if !is_glob(component) {
+                    return vec![if i == 0 || prefix.ends_with('/') {
+                        format!("{prefix}{component}")
+                    } else {
+                        format!("{prefix}/{component}")
+                    }];
+                }
+
+                let dir = if prefix.is_empty() { "." } else { &prefix };
+                let Ok(entries) = fs::read_dir(dir) else {
+                    return vec![];
+                };
+
+                entries
+                    .flatten()
+                    .filter_map(|e| e.file_name().into_string().ok())
+                    .filter(|name| {
+                        (!name.starts_with('.') || component.starts_with('.'))
+                            && matches(component, name)
+                    })
+                    .map(|name| match prefix.as_str() {
+                        "" => name,
+                        p if p.ends_with('/') => format!("{p}{name}"),
+                        p => format!("{p}/{name}"),
+                    })
+                    .filter(|path| last || Path::new(path).is_dir())
+                    .collect()
+            })
+            .collect();
+    }
+
+    let mut paths = paths
+        .into_iter()
+        .filter(|p| Path::new(p).exists())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*.c", "foo.c"));
+        assert!(matches("*", ""));
+        assert!(matches("f?o.c", "foo.c"));
+        assert!(!matches("*.c", "foo.h"));
+        assert!(matches("[abc].c", "b.c"));
+        assert!(matches("[a-c].c", "b.c"));
+        assert!(!matches("[!a-c].c", "b.c"));
+        assert!(matches("[!a-c].c", "d.c"));
+        assert!(matches("[]].c", "].c"));
+        assert!(matches_path("src/*.c", "src/foo.c"));
+        assert!(!matches_path("src/*.c", "src/foo/bar.c"));
+        assert!(!matches_path("*.c", "src/foo.c"));
+        assert!(!matches_path("src/*", "src/.hidden"));
+        assert!(matches("\\*.c", "*.c"));
+        assert!(!matches("\\*.c", "a.c"));
+    }
+
+    #[test]
+    fn test_glob() {
+        assert_eq!(glob("src/gl*.rs"), vec!["src/glob.rs"]);
+        assert_eq!(glob("s?c/makefile/p*.rs"), vec!["src/makefile/pattern.rs"]);
+        assert_eq!(glob("Cargo.toml"), vec!["Cargo.toml"]);
+        assert!(glob("does-not-exist-*").is_empty());
+    }
+}
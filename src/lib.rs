@@ -0,0 +1,186 @@
+//! # omake (Oxidized Make)
+//!
+//! This is an implementation of `make`, written in Rust. The goal is to provide an implementation
+//! of `make` that can be used to process both BSD and GNU makefiles.
+//!
+//! Besides the `omake` binary, the engine can be embedded in other tools. The simplest entry point
+//! is [`run`], which parses a makefile and makes the given targets:
+//!
+//! ```no_run
+//! let args = omake::Args::default();
+//! omake::run("Makefile", &["all"], args).unwrap();
+//! ```
+//!
+//! For more control, a [`Makefile`] can be parsed from any reader (or built up rule by rule) and
+//! then executed. Errors are returned as [`MakeError`]s, which carry the [`Context`] (file and
+//! line) where they occurred, rather than being printed.
+
+pub mod args;
+pub mod context;
+pub mod error;
+mod glob;
+pub mod makefile;
+mod paths;
+pub mod signal;
+pub mod vars;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+pub use args::Args;
+pub use context::Context;
+pub use error::MakeError;
+pub use makefile::{Makefile, Rule, RuleMap};
+pub use vars::Env;
+
+/// An ordered list of filenames used to search for a makefile.
+pub const MAKEFILE_SEARCH: [&str; 6] = [
+    "makefile",
+    "Makefile",
+    "BSDmakefile",
+    "BSDMakefile",
+    "GNUmakefile",
+    "GNUMakefile",
+];
+
+/// Search for a makefile to execute.
+///
+/// We have to take into account that the file system may be case-insensitive. Ideally, we want to
+/// return the proper casing of the makefile (so the file is properly reported when logging), and we
+/// also want to support weirdly-cased makefiles on case-insensitive file systems, such as
+/// `MAKEFILE`. To that end, we first get a directory listing and try to find makefiles from that
+/// list, which would ensure the proper casing is returned. As a fallback, we then iterate through
+/// the `MAKEFILE_SEARCH` list and try to read them from the file system, which will do a
+/// case-insensitive match on case-insensitive file systems, and therefore would return improper
+/// casing (e.g., `MAKEFILE` would be returned as `makefile`, since that would be the first match).
+///
+/// TODO: The first method of inspecting the directory listing is slower, and if that becomes an
+/// issue, perhaps we only do that when verbose logging is enabled?
+pub fn find_makefile() -> Option<PathBuf> {
+    // First, try to find a makefile from the directory listing, which will be a case-sensitive
+    // match. This ensures that if a case-sensitive match is found on a case-insensitive file
+    // system, we will return the proper casing (e.g., if `Makefile` is found, then we won't have
+    // first matched `makefile` and therefore returned the wrong casing).
+    if let Some(cwd_files) = fs::read_dir("./").ok().map(|rd| {
+        rd.flatten()
+            .filter_map(|rd| rd.path().file_name().map(PathBuf::from))
+            .collect::<Vec<_>>()
+    }) {
+        for file in MAKEFILE_SEARCH {
+            let f = PathBuf::from(file);
+            if cwd_files.contains(&f) && f.is_file() {
+                return Some(f);
+            }
+        }
+    }
+
+    // Second, test each file in `MAKEFILE_SEARCH`, which then does a case-insensitive match on
+    // case-insensitive file systems. This is purely for flexibility on case-insensitive file
+    // systems (e.g., so a file named `MAKEFILE` would be matched), however it does result in the
+    // "wrong" casing being logged.
+    for file in MAKEFILE_SEARCH {
+        let f = PathBuf::from(file);
+        if f.is_file() {
+            return Some(f);
+        }
+    }
+
+    None
+}
+
+/// Parse the makefile at `path` and make `targets` (or the default target, if there are none),
+/// using the current process's environment. Unlike the `omake` binary, this does not change
+/// directory for `args.directory`, and it returns the first error rather than exiting. Variable
+/// overrides (e.g., `CC=clang`) are given through `args.overrides`.
+pub fn run(path: impl Into<PathBuf>, targets: &[&str], mut args: Args) -> Result<(), MakeError> {
+    args.targets = targets.iter().map(|t| t.to_string()).collect();
+    let makefile = Makefile::new(path.into(), args, env::vars().collect())?;
+    makefile.execute()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_reader() {
+        let input = "all: missing\n\techo $@\n";
+        let makefile = Makefile::from_reader(
+            "inline.mk".into(),
+            input.as_bytes(),
+            Args::default(),
+            Env::new(),
+        )
+        .unwrap();
+
+        let e = makefile.execute().unwrap_err();
+        assert_eq!(
+            e.msg,
+            "No rule to make target 'missing', required by 'all'."
+        );
+        assert_eq!(e.context.path, Some("inline.mk".into()));
+        assert_eq!(e.context.line_number, 1);
+    }
+
+    #[test]
+    fn test_parse_error_context() {
+        let input = "all:\n\ttrue\nifeq (a,b)\n";
+        let e = Makefile::from_reader(
+            "inline.mk".into(),
+            input.as_bytes(),
+            Args::default(),
+            Env::new(),
+        )
+        .unwrap_err();
+        assert_eq!(e.msg, "Missing 'endif'.");
+        assert_eq!(e.context.path, Some("inline.mk".into()));
+        assert_eq!(e.context.line_number, 3);
+    }
+
+    #[test]
+    fn test_eval() {
+        let input = "$(foreach t,a b,$(eval $(t): ; @echo $(t)))\n";
+        let makefile = Makefile::from_reader(
+            "inline.mk".into(),
+            input.as_bytes(),
+            Args::default(),
+            Env::new(),
+        )
+        .unwrap();
+        assert_eq!(makefile.vars.get(".DEFAULT_GOAL").value, "a");
+
+        // Runaway recursion is an error, rather than a stack overflow.
+        let input = "loop = $$(eval $$(loop))\n$(eval $(loop))\n";
+        let e = Makefile::from_reader(
+            "inline.mk".into(),
+            input.as_bytes(),
+            Args::default(),
+            Env::new(),
+        )
+        .unwrap_err();
+        assert_eq!(e.msg, "Evaluations nested too deeply (over 50).");
+    }
+
+    #[test]
+    fn test_insert_rule() {
+        let mut makefile = Makefile::empty(Args::default(), Env::new());
+        makefile
+            .insert_rule(Rule::new(
+                vec![".PHONY".to_string()],
+                vec!["all".to_string()],
+                vec![],
+            ))
+            .unwrap();
+        makefile
+            .insert_rule(Rule::new(
+                vec!["all".to_string()],
+                vec![],
+                vec!["true".to_string()],
+            ))
+            .unwrap();
+
+        // The phony target is the default goal, and is never up to date.
+        assert!(!makefile.question().unwrap());
+    }
+}
@@ -1,91 +1,75 @@
-//! # omake (Oxidized Make)
-//!
-//! This is an implementation of `make`, written in Rust. The goal is to provide an implementation
-//! of `make` that can be used to process both BSD and GNU makefiles.
-
-mod args;
-mod context;
-mod error;
-mod makefile;
-mod vars;
+//! The `omake` command line interface. The engine itself lives in the library crate.
 
 use std::env;
-use std::fs;
 use std::path::PathBuf;
 
-use clap::Parser;
+use omake::args::GraphFormat;
+use omake::error::{
+    log_entering_directory, log_error, log_info, log_leaving_directory, set_color, set_log_format,
+    MakeError,
+};
+use omake::{find_makefile, signal, Args, Context, Env, Makefile};
 
-use args::Args;
-use context::Context;
-use error::{log_error, log_info};
-use makefile::Makefile;
-use vars::Env;
+const LICENSE: &str = include_str!("../LICENSE");
 
-/// An ordered list of filenames used to search for a makefile.
-const MAKEFILE_SEARCH: [&str; 6] = [
-    "makefile",
-    "Makefile",
-    "BSDmakefile",
-    "BSDMakefile",
-    "GNUmakefile",
-    "GNUMakefile",
-];
+/// The exit status when all targets were made successfully (or, in question mode, when they are all
+/// up to date).
+const EXIT_SUCCESS: i32 = 0;
+/// The exit status in question mode when some target is not up to date.
+const EXIT_OUT_OF_DATE: i32 = 1;
+/// The exit status for any error, such as failing to parse the makefile or to make a target (even
+/// when keeping going).
+const EXIT_FAILURE: i32 = 2;
+
+/// The exit status after an error: `EXIT_FAILURE`, or if we were interrupted by signal `N`,
+/// `128 + N` (like a shell).
+fn failure_status() -> i32 {
+    signal::pending().map_or(EXIT_FAILURE, |signal| 128 + signal)
+}
 
-const LICENSE: &str = include_str!("../LICENSE");
+/// Print an error message and exit with `failure_status`.
+fn exit_with(msg: impl AsRef<str>, context: Option<&Context>) -> ! {
+    log_error(msg, context);
+    std::process::exit(failure_status())
+}
 
-/// Search for a makefile to execute.
-///
-/// We have to take into account that the file system may be case-insensitive. Ideally, we want to
-/// return the proper casing of the makefile (so the file is properly reported when logging), and we
-/// also want to support weirdly-cased makefiles on case-insensitive file systems, such as
-/// `MAKEFILE`. To that end, we first get a directory listing and try to find makefiles from that
-/// list, which would ensure the proper casing is returned. As a fallback, we then iterate through
-/// the `MAKEFILE_SEARCH` list and try to read them from the file system, which will do a
-/// case-insensitive match on case-insensitive file systems, and therefore would return improper
-/// casing (e.g., `MAKEFILE` would be returned as `makefile`, since that would be the first match).
-///
-/// TODO: The first method of inspecting the directory listing is slower, and if that becomes an
-/// issue, perhaps we only do that when verbose logging is enabled?
-fn find_makefile() -> Option<PathBuf> {
-    // First, try to find a makefile from the directory listing, which will be a case-sensitive
-    // match. This ensures that if a case-sensitive match is found on a case-insensitive file
-    // system, we will return the proper casing (e.g., if `Makefile` is found, then we won't have
-    // first matched `makefile` and therefore returned the wrong casing).
-    if let Some(cwd_files) = fs::read_dir("./").ok().map(|rd| {
-        rd.flatten()
-            .filter_map(|rd| rd.path().file_name().map(PathBuf::from))
-            .collect::<Vec<_>>()
-    }) {
-        for file in MAKEFILE_SEARCH {
-            let f = PathBuf::from(file);
-            if cwd_files.contains(&f) && f.is_file() {
-                return Some(f);
-            }
-        }
+/// Parse the makefiles and make the targets, returning the exit status.
+fn build(makefile_fns: &[PathBuf], args: Args) -> Result<i32, MakeError> {
+    let makefile = Makefile::from_files(makefile_fns, args, env::vars().collect::<Env>())?;
+
+    // When printing the data base, nothing is made.
+    if makefile.args.print_data_base {
+        print!("{}", makefile.data_base());
+        return Ok(EXIT_SUCCESS);
     }
 
-    // Second, test each file in `MAKEFILE_SEARCH`, which then does a case-insensitive match on
-    // case-insensitive file systems. This is purely for flexibility on case-insensitive file
-    // systems (e.g., so a file named `MAKEFILE` would be matched), however it does result in the
-    // "wrong" casing being logged.
-    for file in MAKEFILE_SEARCH {
-        let f = PathBuf::from(file);
-        if f.is_file() {
-            return Some(f);
+    // Likewise when printing the dependency graph.
+    match makefile.args.print_graph {
+        Some(GraphFormat::Dot) => {
+            print!("{}", makefile.dot_graph());
+            return Ok(EXIT_SUCCESS);
         }
+        None => (),
     }
 
-    None
-}
+    // In question mode, only report whether the targets are up to date through the exit status.
+    if makefile.args.question {
+        return Ok(if makefile.question()? {
+            EXIT_SUCCESS
+        } else {
+            EXIT_OUT_OF_DATE
+        });
+    }
 
-/// Print an error message and exit with code 2.
-fn exit_with(msg: impl AsRef<str>, context: Option<&Context>) -> ! {
-    log_error(msg, context);
-    std::process::exit(2)
+    makefile.execute()?;
+    Ok(EXIT_SUCCESS)
 }
 
 fn main() {
-    let args = Args::parse();
+    let args = Args::from_env();
+    set_log_format(args.log_format);
+    set_color(!args.no_color);
+    signal::install();
 
     if args.license {
         println!("{}", LICENSE);
@@ -112,17 +96,17 @@ fn main() {
         Some(cwd)
     };
 
-    // Determine the makefile to read.
-    let makefile_fn = match args.file {
-        None => find_makefile().unwrap_or_else(|| exit_with("No makefile found.", None)),
-        Some(ref file) => PathBuf::from(file),
+    // Determine the makefiles to read.
+    let makefile_fns = if args.makefiles.is_empty() {
+        vec![find_makefile().unwrap_or_else(|| exit_with("No makefile found.", None))]
+    } else {
+        args.makefiles.iter().map(PathBuf::from).collect()
     };
 
-    // TODO: Use `make_path` for sub-make invocations. Use `make_name` for logging rather than the
-    // hardcoded `make`.
+    // TODO: Use `make_name` for logging rather than the hardcoded `make`. (Sub-makes are invoked
+    // through `$(MAKE)`, which is set from `current_exe` by the parser.)
     //
-    // // Determine how the current program was invoked. Note that we do not use `current_exe` because,
-    // // at least on Linux, that would resolve symlinks, which is not what we want.
+    // // Determine how the current program was invoked.
     // let make_path: String = env::args().next().unwrap();
     // let make_name: String = PathBuf::from(&make_path)
     //     .file_name()
@@ -130,15 +114,28 @@ fn main() {
     //     .to_string_lossy()
     //     .into();
 
-    // Parse the makefile.
-    let makefile = match Makefile::new(makefile_fn, args, env::vars().collect::<Env>()) {
-        Err(e) => exit_with(e.msg, Some(&e.context)),
-        Ok(m) => m,
-    };
-
-    // Execute the makefile.
-    if let Err(e) = makefile.execute() {
-        exit_with(e.msg, Some(&e.context));
+    // Parse the makefiles and make the targets, announcing the (absolute) working directory
+    // before and after if needed. Sub-makes do this by default.
+    let makelevel = env::var("MAKELEVEL")
+        .ok()
+        .and_then(|l| l.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    let working_dir = args.prints_directory(makelevel).then(|| {
+        env::current_dir()
+            .unwrap_or_else(|e| exit_with(format!("Failed to get cwd ({}).", e), None))
+    });
+    if let Some(dir) = &working_dir {
+        log_entering_directory(dir);
+    }
+    let status = build(&makefile_fns, args).unwrap_or_else(|e| {
+        log_error(e.msg, Some(&e.context));
+        failure_status()
+    });
+    if let Some(dir) = &working_dir {
+        log_leaving_directory(dir);
+    }
+    if status != EXIT_SUCCESS {
+        std::process::exit(status);
     }
 
     // Go back to the original directory, if we changed directory previously.
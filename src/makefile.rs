@@ -1,77 +1,540 @@
+mod assignment;
+mod conditional;
+mod data_base;
 mod expand;
+mod graph;
+mod jobserver;
+mod manifest;
+mod pattern;
 mod rule_map;
+mod shell;
 
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
 
 use crate::args::Args;
 use crate::context::Context;
-use crate::error::MakeError;
-use crate::vars::{Env, Vars};
+use crate::error::{log_warn, MakeError};
+use crate::glob::{glob, is_glob};
+use crate::paths;
+use crate::vars::{Env, Origin, Vars, DEFAULT_SUFFIXES};
 
-use expand::expand;
-use rule_map::{Rule, RuleMap};
+use assignment::{Assignment, Operator};
+use conditional::{Condition, Conditional, Directive};
+use expand::Expander;
+use jobserver::Jobserver;
+pub use manifest::HashFn;
+use pattern::{is_pattern, match_pattern, substitute};
+pub use rule_map::{Rule, RuleMap};
 
 const COMMENT_INDICATOR: char = '#';
 
+/// How deeply `$(eval ...)` may be nested, which stops runaway recursion.
+const MAX_EVAL_DEPTH: usize = 50;
+
+/// The directories searched for included makefiles after those given with `-I`, as in GNU make.
+pub const INCLUDE_DIRS: &[&str] = if cfg!(unix) {
+    &["/usr/local/include", "/usr/gnu/include", "/usr/include"]
+} else {
+    &[]
+};
+
+/// Get the canonical form of a makefile's path, for identifying it, which is the path itself if it
+/// can't be resolved (e.g., for a makefile which is not read from a file).
+fn canonicalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Strip any comment (from an unescaped `#` to the end of the line) from a line. A `#` is escaped
+/// by a backslash, and any backslashes before a `#` are halved, so `\\#` is a backslash followed by
+/// a comment.
+fn strip_comment(line: &str) -> Cow<'_, str> {
+    if !line.contains(COMMENT_INDICATOR) {
+        return Cow::Borrowed(line);
+    }
+
+    let mut stripped = String::with_capacity(line.len());
+    let mut backslashes = 0;
+    for ch in line.chars() {
+        match ch {
+            '\\' => backslashes += 1,
+            COMMENT_INDICATOR => {
+                stripped.push_str(&"\\".repeat(backslashes / 2));
+                if backslashes % 2 == 0 {
+                    return Cow::Owned(stripped);
+                }
+                stripped.push(ch);
+                backslashes = 0;
+            }
+            _ => {
+                stripped.push_str(&"\\".repeat(backslashes));
+                stripped.push(ch);
+                backslashes = 0;
+            }
+        }
+    }
+
+    stripped.push_str(&"\\".repeat(backslashes));
+    Cow::Owned(stripped)
+}
+
+/// Check whether a line is continued onto the next, by ending in a backslash. A doubled backslash
+/// is literal, and so is a backslash followed by whitespace.
+fn is_continued(line: &str) -> bool {
+    line.chars().rev().take_while(|&ch| ch == '\\').count() % 2 == 1
+}
+
+/// Join a continuation line onto `line` (which must be continued). In recipes (and definitions),
+/// the backslash-newline is kept, so it is passed to the shell, and a leading recipe prefix on the
+/// continuation is removed. Elsewhere, the backslash-newline and any surrounding whitespace
+/// collapse into a single space.
+fn join_continuation(line: &mut String, next: &str, verbatim: Option<&str>) {
+    match verbatim {
+        Some(recipe_prefix) => {
+            line.push('\n');
+            line.push_str(next.strip_prefix(recipe_prefix).unwrap_or(next));
+        }
+        None => {
+            line.pop();
+            line.truncate(line.trim_end().len());
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(next.trim_start());
+        }
+    }
+}
+
+/// Split a rule definition at the `:` which separates the targets from the prerequisites, ignoring
+/// any inside variable references (e.g., `$(subst :,-,$(NAME)): config.h`) or Windows drive
+/// letters (e.g., `C:\obj\main.o: main.c`).
+fn split_rule(line: &str) -> Option<(&str, &str)> {
+    split_unreferenced(line, |i, ch| ch == ':' && !paths::is_drive_colon(line, i))
+}
+
+/// Split a line around the first character for which `is_separator` (given its byte index and the
+/// character) is true, ignoring any inside variable references.
+fn split_unreferenced(
+    line: &str,
+    is_separator: impl Fn(usize, char) -> bool,
+) -> Option<(&str, &str)> {
+    let mut depth: i32 = 0;
+    for (i, ch) in line.char_indices() {
+        match ch {
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth -= 1,
+            _ if depth == 0 && is_separator(i, ch) => {
+                return Some((&line[..i], &line[i + ch.len_utf8()..]))
+            }
+            _ => (),
+        }
+    }
+
+    None
+}
+
+/// Split a list of words on whitespace, ignoring any inside variable references (e.g., so that
+/// `$(wildcard $*.c)` is a single word).
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut rest = line.trim_start();
+    while let Some((word, after)) = split_unreferenced(rest, |_, ch| ch.is_whitespace()) {
+        words.push(word.to_string());
+        rest = after.trim_start();
+    }
+    if !rest.is_empty() {
+        words.push(rest.to_string());
+    }
+
+    words
+}
+
+/// Check whether `name` can be passed through the environment to the shell, i.e., it is made of
+/// letters, digits, and underscores, and doesn't start with a digit. Only such variables are
+/// exported by `export_all`.
+fn is_env_name(name: &str) -> bool {
+    !name.starts_with(|ch: char| ch.is_ascii_digit())
+        && !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+/// The targets which a special target (such as `.IGNORE`) applies to. Special targets without any
+/// prerequisites apply to every target.
+#[derive(Debug, Default)]
+enum TargetSet {
+    #[default]
+    None,
+    Some(HashSet<String>),
+    All,
+}
+
+impl TargetSet {
+    fn extend(&mut self, targets: &[String]) {
+        match self {
+            Self::All => (),
+            _ if targets.is_empty() => *self = Self::All,
+            Self::None => *self = Self::Some(targets.iter().cloned().collect()),
+            Self::Some(set) => set.extend(targets.iter().cloned()),
+        }
+    }
+
+    fn is_all(&self) -> bool {
+        matches!(self, Self::All)
+    }
+
+    fn contains(&self, target: &str) -> bool {
+        match self {
+            Self::None => false,
+            Self::Some(set) => set.contains(target),
+            Self::All => true,
+        }
+    }
+}
+
+/// A multi-line variable definition (between `define` and `endef`) which is being parsed.
+#[derive(Debug)]
+struct Define {
+    name: String,
+    operator: Operator,
+    origin: Origin,
+    lines: Vec<String>,
+    /// How many nested `define` directives (which are part of the value) are open.
+    depth: usize,
+    context: Context,
+}
+
 /// The internal representation of a makefile.
 #[derive(Debug)]
 pub struct Makefile {
     pub args: Args,
     rule_map: RuleMap,
-    default_target: Option<String>,
+
+    /// Targets which are not files, declared using the special target `.PHONY`.
+    phony: HashSet<String>,
+    /// Targets whose recipes' errors are ignored, declared using the special target `.IGNORE`.
+    ignore: TargetSet,
+    /// Targets whose recipes are not echoed, declared using the special target `.SILENT`.
+    silent: TargetSet,
+    /// Whether targets should be deleted when their recipes fail, as set using the special target
+    /// `.DELETE_ON_ERROR`.
+    delete_on_error: bool,
+    /// Whether each recipe is run as a single shell script, rather than one invocation of the shell
+    /// per line, as set using the special target `.ONESHELL`.
+    one_shell: bool,
+    /// Targets whose prerequisites are made one at a time, as if separated by `.WAIT`, declared
+    /// using the special target `.NOTPARALLEL`. Without prerequisites, the whole build is serial.
+    not_parallel: TargetSet,
+    /// Targets which are deleted once they are no longer needed, as if they were only made by a
+    /// chain of pattern rules, declared using the special target `.INTERMEDIATE`.
+    intermediate: HashSet<String>,
+    /// Targets which are never deleted as intermediate files, declared using the special target
+    /// `.SECONDARY`. Without prerequisites, no intermediate files are deleted.
+    secondary: TargetSet,
+    /// The suffixes which are known for suffix rules, as set using the special target `.SUFFIXES`.
+    suffixes: Vec<String>,
+    /// Whether the prerequisites of rules (defined from now on) are expanded a second time when
+    /// they are used, as set using the special target `.SECONDEXPANSION`.
+    second_expansion: bool,
+
+    /// Variable assignments which only apply while making a particular target (and its
+    /// prerequisites), in definition order.
+    target_vars: HashMap<String, Vec<Assignment>>,
+    /// Like `target_vars`, but for targets matching a pattern (e.g., `%.o: CFLAGS += -Wall`).
+    pattern_vars: Vec<(String, Assignment)>,
+
+    /// Search directories for prerequisites matching a pattern, from `vpath` directives.
+    vpaths: Vec<(String, Vec<String>)>,
+
+    /// Whether every variable is exported to the environment of recipes, as set using `export`
+    /// alone or the special target `.EXPORT_ALL_VARIABLES` (and cleared using `unexport` alone).
+    export_all: bool,
+    /// Variables which are exported (`true`) or unexported (`false`) regardless of `export_all`,
+    /// using the `export` and `unexport` directives. Variables from the environment and the
+    /// command line start out exported.
+    exports: HashMap<String, bool>,
+
+    /// The pool of job slots shared with any sub-makes, when running jobs in parallel.
+    jobserver: Option<Jobserver>,
+
+    /// The function for hashing the contents of prerequisites, with `--check-hashes`.
+    hash_fn: HashFn,
 
     // Parser state.
     pub vars: Vars,
     current_rule: Option<Rule>,
+    conditionals: Vec<Conditional>,
+    define: Option<Define>,
+    /// How deeply `$(eval ...)` is nested.
+    eval_depth: usize,
+    /// The makefiles which are being parsed (by canonical path, along with the name they were
+    /// given), from the outermost to the current one.
+    include_stack: Vec<(PathBuf, String)>,
+    /// Each makefile which has been parsed (by canonical path), which are not parsed again.
+    included: HashSet<PathBuf>,
     context: Context,
 }
 
 impl Makefile {
     /// Principal interface for reading and parsing a makefile.
     pub fn new(makefile_fn: PathBuf, args: Args, env: Env) -> Result<Self, MakeError> {
+        Self::from_files(&[makefile_fn], args, env)
+    }
+
+    /// Read and parse several makefiles in order, into one set of variables and rules (e.g., for
+    /// `-f base.mk -f overrides.mk`), so later makefiles see (and may replace) the definitions of
+    /// earlier ones. A makefile named `-` is read from `STDIN`.
+    pub fn from_files(makefile_fns: &[PathBuf], args: Args, env: Env) -> Result<Self, MakeError> {
+        let mut makefile = Self::empty(args, env);
+        for makefile_fn in makefile_fns {
+            if makefile_fn.as_os_str() == "-" {
+                makefile.read(makefile_fn.clone(), io::stdin().lock())?;
+                continue;
+            }
+
+            // Open the makefile and run it through the parser.
+            let file = File::open(makefile_fn).map_err(|e| {
+                MakeError::new(
+                    format!("Could not read makefile ({}).", e),
+                    makefile_fn.clone().into(),
+                )
+            })?;
+            makefile.read(makefile_fn.clone(), BufReader::new(file))?;
+        }
+
+        Ok(makefile)
+    }
+
+    /// Parse a makefile from any reader (e.g., an in-memory string), given the name to use for it
+    /// when reporting errors.
+    pub fn from_reader<R: BufRead>(
+        makefile_fn: PathBuf,
+        stream: R,
+        args: Args,
+        env: Env,
+    ) -> Result<Self, MakeError> {
+        let mut makefile = Self::empty(args, env);
+        makefile.read(makefile_fn, stream)?;
+        Ok(makefile)
+    }
+
+    /// Parse a top-level makefile (i.e., one which is not included by another) from a reader.
+    fn read<R: BufRead>(&mut self, makefile_fn: PathBuf, stream: R) -> Result<(), MakeError> {
+        self.add_to_makefile_list(&makefile_fn);
+        let canonical = canonicalize(&makefile_fn);
+        self.included.insert(canonical.clone());
+        self.include_stack
+            .push((canonical, makefile_fn.to_string_lossy().into_owned()));
+        self.context = makefile_fn.into();
+        let result = self.parse(stream);
+        self.include_stack.pop();
+        result
+    }
+
+    /// Create a makefile without any rules, which can then be added programmatically using
+    /// `insert_rule`.
+    pub fn empty(mut args: Args, env: Env) -> Self {
+        // Without the builtin variables, the builtin rules (which use them) can't work either.
+        if args.no_builtin_variables {
+            args.no_builtin_rules = true;
+        }
+
         // Initialize the `Makefile` struct with default values.
+        let jobserver = Jobserver::from_args(&mut args);
+        let makeflags = args.makeflags();
+        let exports = env
+            .keys()
+            .filter(|k| *k != "SHELL")
+            .map(|k| (k.clone(), true))
+            .collect();
         let mut makefile = Self {
             args,
             rule_map: RuleMap::new(),
-            default_target: None,
+            phony: HashSet::new(),
+            ignore: TargetSet::default(),
+            silent: TargetSet::default(),
+            delete_on_error: false,
+            one_shell: false,
+            not_parallel: TargetSet::default(),
+            intermediate: HashSet::new(),
+            secondary: TargetSet::default(),
+            suffixes: DEFAULT_SUFFIXES.iter().map(|s| s.to_string()).collect(),
+            second_expansion: false,
+            target_vars: HashMap::new(),
+            pattern_vars: vec![],
+            vpaths: vec![],
+            export_all: false,
+            exports,
+            jobserver,
+            hash_fn: manifest::fnv1a,
             vars: env.into(),
             current_rule: None,
-            context: makefile_fn.clone().into(),
+            conditionals: vec![],
+            define: None,
+            eval_depth: 0,
+            include_stack: vec![],
+            included: HashSet::new(),
+            context: Context::new(),
         };
 
-        // Open the makefile and run it through the parser.
-        let file = File::open(&makefile_fn).map_err(|e| {
-            MakeError::new(
-                format!("Could not read makefile ({}).", e),
-                makefile_fn.into(),
-            )
-        })?;
-        makefile.parse(BufReader::new(file))?;
+        // Without the builtin rules, no suffixes are known for suffix rules until they are added
+        // using `.SUFFIXES`.
+        if makefile.args.no_builtin_rules {
+            makefile.suffixes.clear();
+            for k in ["SUFFIXES", ".SUFFIXES"] {
+                makefile
+                    .vars
+                    .set_with_origin(k, "", false, Origin::Default)
+                    .expect("valid variable name");
+            }
+        }
+        if makefile.args.no_builtin_variables {
+            makefile.vars.remove_builtins();
+        }
+
+        // Flags are passed down to sub-makes through `MAKEFLAGS`, which replaces any inherited
+        // value, since inherited flags have already been merged into `args`.
+        makefile
+            .vars
+            .set("MAKEFLAGS".to_string(), makeflags, false)
+            .expect("valid variable name");
 
-        Ok(makefile)
+        // Recipes invoke sub-makes through `$(MAKE)`, which is this program. `MAKELEVEL` is the
+        // depth of recursion, which is inherited (and incremented) through the environment.
+        // `CURDIR` is the working directory (after any `-C`), and is never inherited.
+        // `MAKEFILE_LIST` is filled in as makefiles are parsed. `MAKECMDGOALS` lists the targets
+        // given on the command line, so makefiles can act on them (e.g.,
+        // `ifeq ($(MAKECMDGOALS),clean)`).
+        let make = env::current_exe()
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| "omake".to_string());
+        let makelevel = makefile.vars.get("MAKELEVEL").value.trim().parse::<usize>();
+        let curdir = env::current_dir()
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        for (k, v) in [
+            ("MAKE", make),
+            ("MAKELEVEL", makelevel.unwrap_or(0).to_string()),
+            ("CURDIR", curdir),
+            ("MAKEFILE_LIST", String::new()),
+            ("MAKECMDGOALS", makefile.args.targets.join(" ")),
+        ] {
+            makefile
+                .vars
+                .set(k.to_string(), v, false)
+                .expect("valid variable name");
+        }
+
+        // Apply variable overrides from the command line, which makefile assignments can't replace.
+        for assignment in makefile.args.overrides.clone() {
+            let result = match assignment::parse(&assignment) {
+                Some((name, operator, value)) => makefile
+                    .assign(name, operator, value, Origin::CommandLine)
+                    .map(|()| {
+                        makefile.exports.insert(name.trim().to_string(), true);
+                    }),
+                None => Err(MakeError::new("Not an assignment.", Context::new())),
+            };
+            if let Err(e) = result {
+                log_warn(
+                    format!("Ignoring variable override `{assignment}` ({}).", e.msg),
+                    None,
+                );
+            }
+        }
+
+        makefile
     }
 
-    /// Iterate over the makefile's lines, call `parse_line` to handle the actual parsing logic, and
-    /// manage context.
+    /// Parse the makefile's lines (see `parse_lines`), and make sure the last rule, as well as any
+    /// conditionals and definitions, end with the file.
     fn parse<R: BufRead>(&mut self, stream: R) -> Result<(), MakeError> {
         self.current_rule = None;
+        self.conditionals.clear();
+        self.define = None;
+
+        self.parse_lines(stream.lines(), true)?;
+
+        // Terminate the trailing rule, if any.
+        if let Some(rule) = self.current_rule.take() {
+            self.insert_rule(rule)?;
+        }
+
+        // Conditionals (and definitions) must be closed in the same file where they are opened.
+        if let Some(conditional) = self.conditionals.pop() {
+            return Err(MakeError::new("Missing 'endif'.", conditional.context));
+        }
+        if let Some(define) = self.define.take() {
+            return Err(MakeError::new("Missing 'endef'.", define.context));
+        }
+
+        Ok(())
+    }
+
+    /// Join continuation lines into logical lines, and call `parse_line` on each of them. If
+    /// `numbered`, the context is pointed at each logical line as it is parsed; otherwise (e.g.,
+    /// for `$(eval ...)`), it is left alone.
+    fn parse_lines(
+        &mut self,
+        lines: impl Iterator<Item = io::Result<String>>,
+        numbered: bool,
+    ) -> Result<(), MakeError> {
+        // A line which is continued (see `is_continued`), along with its line number, and for a
+        // recipe line (or definition), the recipe prefix to remove from continuations.
+        let mut continued: Option<(usize, String, Option<String>)> = None;
 
-        for (i, result) in stream.lines().enumerate() {
-            // Set the context line number and extract the line.
-            self.context.line_number = i + 1;
-            let line = result.map_err(|e| MakeError::new(e.to_string(), self.context.clone()))?;
-            self.context.line = Some(line.clone());
+        for (i, result) in lines.enumerate() {
+            let line = result.map_err(|e| {
+                self.context.line_number = i + 1;
+                MakeError::new(e.to_string(), self.context.clone())
+            })?;
+
+            // Join continuation lines into a single logical line, which has the line number of its
+            // first physical line. Whether it is a recipe line is decided by the first line.
+            let (line_number, line, verbatim) = match continued.take() {
+                None if is_continued(&line) => {
+                    let recipe_prefix = self.vars.get(".RECIPEPREFIX").value.clone();
+                    let verbatim = if self.define.is_some() {
+                        Some(String::new())
+                    } else if self.current_rule.is_some() && line.starts_with(&recipe_prefix) {
+                        Some(recipe_prefix)
+                    } else {
+                        None
+                    };
+                    (i + 1, line, verbatim)
+                }
+                None => (i + 1, line, None),
+                Some((line_number, mut joined, verbatim)) => {
+                    join_continuation(&mut joined, &line, verbatim.as_deref());
+                    (line_number, joined, verbatim)
+                }
+            };
+            if is_continued(&line) {
+                continued = Some((line_number, line, verbatim));
+                continue;
+            }
 
-            // Parse the line.
+            // Set the context line number and parse the line.
+            if numbered {
+                self.context.line_number = line_number;
+                self.context.line = Some(line.clone());
+            }
             self.parse_line(line)?;
         }
 
-        // Always push two blank lines at the end to terminate trailing rules, even if the last rule
-        // contained a trailing backslash.
-        self.parse_line("".to_string())?;
-        self.parse_line("".to_string())?;
+        // A backslash on the last line has nothing to continue onto.
+        if let Some((line_number, line, _)) = continued {
+            if numbered {
+                self.context.line_number = line_number;
+                self.context.line = Some(line.clone());
+            }
+            self.parse_line(line)?;
+        }
 
         Ok(())
     }
@@ -81,14 +544,65 @@ impl Makefile {
     /// newlines and semicolons, and also managing state, such as variable assignments and
     /// annotating when the parser moves in-to and out-of a rule definition.
     fn parse_line(&mut self, line: String) -> Result<(), MakeError> {
+        // Lines of a multi-line definition are taken verbatim, until the matching `endef`, which
+        // must be alone on its line (other than a comment). Nested definitions are part of the
+        // value.
+        if let Some(define) = &mut self.define {
+            let (directive, rest) = line
+                .trim()
+                .split_once(char::is_whitespace)
+                .unwrap_or((line.trim(), ""));
+            match directive {
+                "define" => define.depth += 1,
+                "endef" if define.depth > 0 => define.depth -= 1,
+                "endef" => {
+                    let rest = rest.trim_start();
+                    if !rest.is_empty() && !rest.starts_with(COMMENT_INDICATOR) {
+                        return Err(MakeError::new(
+                            "Extraneous text after 'endef' directive.",
+                            self.context.clone(),
+                        ));
+                    }
+
+                    let define = self.define.take().expect("known to be defining");
+                    let value = define.lines.join("\n");
+                    return self.assign(&define.name, define.operator, &value, define.origin);
+                }
+                _ => (),
+            }
+
+            define.lines.push(line);
+            return Ok(());
+        }
+
+        // Handle conditional directives, which may appear anywhere (even within a recipe), unless
+        // they start with the recipe prefix.
+        if !line.starts_with(&self.vars.get(".RECIPEPREFIX").value) {
+            if let Some(directive) = conditional::parse_directive(&line) {
+                let directive = directive.map_err(|e| MakeError::new(e, self.context.clone()))?;
+                return self.handle_directive(directive);
+            }
+        }
+
+        // Skip lines in conditional branches which are not taken.
+        if !self.is_active() {
+            return Ok(());
+        }
+
         // Handle recipe lines.
         let recipe_prefix = &self.vars.get(".RECIPEPREFIX").value;
         if line.starts_with(recipe_prefix) {
             // If line starts with the recipe prefix, then push it to the current rule.
             match &mut self.current_rule {
-                None => return Err(MakeError::new("recipe without rule", self.context.clone())),
+                None => {
+                    return Err(MakeError::new(
+                        "Recipe commences before first target.",
+                        self.context.clone(),
+                    ))
+                }
                 Some(r) => {
-                    // Strip the recipe prefix first.
+                    // Strip the recipe prefix first. Note that recipe lines are stored unexpanded,
+                    // since they are expanded when executed (when automatic variables are known).
                     let cmd = line
                         .strip_prefix(recipe_prefix)
                         .expect("line known to start with a recipe prefix")
@@ -96,40 +610,70 @@ impl Makefile {
                         .to_string();
 
                     if !cmd.is_empty() {
-                        r.recipe.push(
-                            expand(cmd.as_str(), &self.vars)
-                                .map_err(|e| MakeError::new(e, self.context.clone()))?,
-                        );
+                        r.recipe.push(cmd);
                     }
                 }
             }
             return Ok(());
         }
 
-        // Anything other than recipe lines terminate a rule definition.
+        // Comments are stripped from all other lines. Recipe lines are passed to the shell as is,
+        // so it handles any `#` in them.
+        let raw = line;
+        let line = strip_comment(&raw).into_owned();
+
+        // Ignore blank lines (including those which were only a comment), which may appear among
+        // the lines of a recipe.
+        let trimmed_line = line.trim();
+        if trimmed_line.is_empty() {
+            return Ok(());
+        }
+
+        // Anything else terminates a rule definition.
+        let in_rule = self.current_rule.is_some();
         if let Some(rule) = self.current_rule.take() {
-            // If there is no default target, see if we can assign one.
-            if self.default_target.is_none() {
-                for target in rule.targets.iter() {
-                    // Set default target if none is specified and this is a normal target.
-                    if self.default_target.is_none() && !target.starts_with('.') {
-                        self.default_target = Some(target.clone());
+            self.insert_rule(rule)?;
+        }
+
+        // Handle directives, unless the line assigns a variable of the same name (e.g.,
+        // `include = main.mk`), as in GNU make.
+        let (directive, rest) = trimmed_line
+            .split_once(char::is_whitespace)
+            .unwrap_or((trimmed_line, ""));
+        let directive = if is_assignment_to(&line, directive) {
+            ""
+        } else {
+            directive
+        };
+        match directive {
+            "include" => return self.include(rest, false),
+            "-include" | "sinclude" => return self.include(rest, true),
+            "vpath" => return self.vpath(rest),
+            "export" | "unexport" => return self.export(rest, directive == "export"),
+            "define" => return self.define(rest, Origin::File),
+            "override" => {
+                if let Some(header) = rest.strip_prefix("define") {
+                    if header.is_empty() || header.starts_with(char::is_whitespace) {
+                        return self.define(header, Origin::Override);
                     }
                 }
+                if let Some((name, operator, value)) = assignment::parse(rest) {
+                    return self.assign(name, operator, value, Origin::Override);
+                }
             }
-
-            // Add the rule to the `rule_map`.
-            self.rule_map.insert(rule)?;
+            _ => (),
         }
 
-        // Ignore pure comments and blank lines.
-        let trimmed_line = line.trim();
-        if trimmed_line.starts_with(COMMENT_INDICATOR) || trimmed_line.is_empty() {
-            return Ok(());
+        // Handle variable assignments.
+        if let Some((name, operator, value)) = assignment::parse(&line) {
+            return self.assign(name, operator, value, Origin::File);
         }
 
-        // Handle rule definitions.
-        if let Some((targets, mut deps)) = line.split_once(':') {
+        // Handle rule definitions. Unlike recipes (which are expanded when they are executed), the
+        // targets and prerequisites are expanded immediately, so they see variables as defined up
+        // to this line: a recursive (`=`) variable which refers to a variable defined later in the
+        // makefile expands without it.
+        if let Some((targets, mut deps)) = split_rule(&line) {
             // First, if deps start with another `:`, then this is a double-colon rule, so we should
             // mark it as such.
             let mut double_colon = false;
@@ -140,75 +684,747 @@ impl Makefile {
                 }
             }
 
+            // This could be a target-specific variable assignment, rather than a rule (unless the
+            // `=` is part of a recipe after a semicolon), which may use the `override` directive.
+            let (assignment, origin) = match deps.trim_start().strip_prefix("override") {
+                Some(rest) if rest.starts_with(char::is_whitespace) => (rest, Origin::Override),
+                _ => (deps, Origin::File),
+            };
+            if let Some((name, operator, value)) = assignment::parse(assignment) {
+                if !name.contains(';') {
+                    return self.assign_target_vars(targets, name, operator, value, origin);
+                }
+            }
+
             // There could be a semicolon after prerequisites, in which case we should parse
-            // everything after that as a rule line.
-            let rule = deps.split_once(';').map(|(d, r)| {
+            // everything after that as a rule line. Semicolons in variable references (e.g.,
+            // `$(subst ;, ,$(LIST))`) are part of the prerequisites, and any after the first are
+            // part of the recipe, so quoting in the recipe is left to the shell. Like other recipe
+            // lines, it is taken from before comments were stripped.
+            let rule = split_unreferenced(deps, |_, ch| ch == ';').map(|(d, _)| {
                 deps = d;
-                r
+                split_rule(&raw)
+                    .and_then(|(_, d)| split_unreferenced(d, |_, ch| ch == ';'))
+                    .map_or("", |(_, r)| r)
             });
 
+            // Prerequisites after a `|` are order-only. With secondary expansion, the prerequisites
+            // are expanded again when the rule is used, so any references left after this
+            // expansion (e.g., from `$$(wildcard $$*.c)`) are kept whole.
+            // A second `:` makes this a static pattern rule (e.g., `$(OBJS): %.o: %.c`), where each
+            // target matches the target pattern, and the prerequisites are patterns into which its
+            // stem is substituted.
+            let static_pattern = match split_rule(deps) {
+                None => None,
+                Some((pattern, rest)) => {
+                    deps = rest;
+                    let pattern = self.expand(pattern)?;
+                    match pattern.split_whitespace().collect::<Vec<_>>().as_slice() {
+                        [pattern] if is_pattern(pattern) => Some(pattern.to_string()),
+                        _ => {
+                            return Err(MakeError::new(
+                                format!("Invalid target pattern '{}'.", pattern.trim()),
+                                self.context.clone(),
+                            ))
+                        }
+                    }
+                }
+            };
+
+            let deps = self.expand(deps)?;
+            let (deps, order_only) = deps.split_once('|').unwrap_or((&deps, ""));
+            let second_expansion = self.second_expansion;
+            let split = |words: &str| match second_expansion {
+                true => split_words(words),
+                false => words.split_whitespace().map(|s| s.to_string()).collect(),
+            };
+
             self.current_rule = Some(Rule {
-                targets: expand(targets, &self.vars)
-                    .map_err(|e| MakeError::new(e, self.context.clone()))?
-                    .split_whitespace()
-                    .map(|s| s.to_string())
-                    .collect(),
-                prerequisites: expand(deps, &self.vars)
-                    .map_err(|e| MakeError::new(e, self.context.clone()))?
+                targets: self
+                    .expand(targets)?
                     .split_whitespace()
                     .map(|s| s.to_string())
                     .collect(),
+                prerequisites: split(deps),
+                order_only: split(order_only),
                 recipe: vec![],
                 context: self.context.clone(),
                 double_colon,
+                empty_recipe: false,
+                second_expansion,
+                static_pattern,
             });
 
-            // Add rule line if we found one.
-            if let Some(r) = rule {
-                self.parse_line(format!("{}{}", self.vars.get(".RECIPEPREFIX").value, r))?;
+            // Add rule line if we found one. A semicolon with nothing after it gives the rule an
+            // empty recipe, which stops implicit rules from being used for its targets.
+            match rule {
+                Some(r) if r.trim().is_empty() => {
+                    if let Some(rule) = &mut self.current_rule {
+                        rule.empty_recipe = true;
+                    }
+                }
+                Some(r) => {
+                    self.parse_line(format!("{}{}", self.vars.get(".RECIPEPREFIX").value, r))?;
+                }
+                None => (),
             }
 
             return Ok(());
         }
 
-        // Handle variable assignments.
-        if let Some((k, v)) = line.split_once('=') {
-            if let Err(e) = self.vars.set(
-                k,
-                &expand(v.trim_start(), &self.vars)
-                    .map_err(|e| MakeError::new(e, self.context.clone()))?,
-                false,
-            ) {
-                return Err(MakeError::new(e, self.context.clone()));
-            };
+        // Lines which expand to nothing (e.g., `$(info ...)`) are only evaluated for their side
+        // effects. Otherwise, throw error if line is not recognizable.
+        if self.expand(&line)?.trim().is_empty() {
             return Ok(());
         }
 
-        // Otherwise, throw error if line is not recognizable.
+        // The most common mistake is indenting a recipe with spaces.
+        if in_rule && line.starts_with(' ') {
+            let recipe_prefix = &self.vars.get(".RECIPEPREFIX").value;
+            let recipe_prefix = match recipe_prefix.as_str() {
+                "\t" => "TAB".to_string(),
+                prefix => format!("`{prefix}`"),
+            };
+            return Err(MakeError::new(
+                format!("Missing separator (did you mean {recipe_prefix} instead of spaces?)."),
+                self.context.clone(),
+            ));
+        }
         Err(MakeError::new("Invalid line type.", self.context.clone()))
     }
 
-    /// Principal interface for executing a parsed makefile, given a list of targets.
-    pub fn execute(&self) -> Result<(), MakeError> {
-        let mut targets = self.args.targets.clone();
+    /// Assign a variable, where the name and value are unexpanded. The operator determines whether
+    /// the value is expanded now, or each time the variable is used. Unless the assignment itself
+    /// comes from the command line (or uses `override`), it is ignored for variables set on the
+    /// command line.
+    fn assign(
+        &mut self,
+        name: &str,
+        operator: Operator,
+        value: &str,
+        origin: Origin,
+    ) -> Result<(), MakeError> {
+        let name = self.expand(name)?;
+        let name = name.trim();
 
-        // Set targets list to default target if none were provided.
-        if targets.is_empty() {
-            match &self.default_target {
+        // Variables from the command line take precedence over assignments in the makefile, unless
+        // the `override` directive is used.
+        if !origin.may_replace(self.vars.get(name).origin) {
+            return Ok(());
+        }
+
+        let (value, recursive) = match operator {
+            Operator::Recursive => (value.to_string(), true),
+            Operator::Simple => (self.expand(value)?, false),
+            Operator::Conditional => {
+                if self.vars.is_defined(name) {
+                    return Ok(());
+                }
+                (value.to_string(), true)
+            }
+            Operator::Append => {
+                if self.vars.is_defined(name) {
+                    let var = self.vars.get(name);
+                    let (old, recursive) = (var.value.clone(), var.recursive);
+                    let value = if recursive {
+                        value.to_string()
+                    } else {
+                        self.expand(value)?
+                    };
+
+                    if old.is_empty() {
+                        (value, recursive)
+                    } else {
+                        (format!("{old} {value}"), recursive)
+                    }
+                } else {
+                    (value.to_string(), true)
+                }
+            }
+        };
+
+        self.vars
+            .set_with_origin(name, &value, recursive, origin)
+            .map_err(|e| MakeError::new(e, self.context.clone()))
+    }
+
+    /// Start a multi-line variable definition, given the header after the `define` directive (e.g.,
+    /// `NAME :=`). The rest of the definition is collected by `parse_line`.
+    fn define(&mut self, header: &str, origin: Origin) -> Result<(), MakeError> {
+        let (name, operator) = assignment::parse_define(header)
+            .map_err(|e| MakeError::new(e, self.context.clone()))?;
+
+        self.define = Some(Define {
+            name: name.to_string(),
+            operator,
+            origin,
+            lines: vec![],
+            depth: 0,
+            context: self.context.clone(),
+        });
+        Ok(())
+    }
+
+    /// Record a target-specific (or pattern-specific) variable assignment for each of the targets.
+    /// Like normal assignments, the value of a simple assignment is expanded now.
+    fn assign_target_vars(
+        &mut self,
+        targets: &str,
+        name: &str,
+        operator: Operator,
+        value: &str,
+        origin: Origin,
+    ) -> Result<(), MakeError> {
+        let assignment = Assignment {
+            name: self.expand(name)?.trim().to_string(),
+            operator,
+            value: match operator {
+                Operator::Simple => self.expand(value)?,
+                _ => value.to_string(),
+            },
+            origin,
+        };
+
+        for target in self.expand(targets)?.split_whitespace() {
+            if is_pattern(target) {
+                self.pattern_vars
+                    .push((target.to_string(), assignment.clone()));
+            } else {
+                self.target_vars
+                    .entry(target.to_string())
+                    .or_default()
+                    .push(assignment.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the variable assignments specific to a target, in the order they should be applied, so
+    /// that later ones take precedence: first those for matching patterns (with longer stems first,
+    /// so that more specific patterns win), then those for the target itself.
+    pub fn target_vars(&self, target: &str) -> Vec<&Assignment> {
+        let mut patterns: Vec<_> = self
+            .pattern_vars
+            .iter()
+            .filter_map(|(pattern, assignment)| {
+                match_pattern(pattern, target).map(|stem| (stem.len(), assignment))
+            })
+            .collect();
+        patterns.sort_by_key(|(stem, _)| std::cmp::Reverse(*stem));
+
+        patterns
+            .into_iter()
+            .map(|(_, assignment)| assignment)
+            .chain(self.target_vars.get(target).into_iter().flatten())
+            .collect()
+    }
+
+    /// Parse each of the (expanded and globbed) files as part of this makefile. Missing files are
+    /// an error, unless `optional` is set (for `-include`). Each file is only parsed once, so
+    /// including it again (e.g., a common file included by several others) does nothing, except
+    /// that a file including itself (directly or indirectly) is an error.
+    fn include(&mut self, files: &str, optional: bool) -> Result<(), MakeError> {
+        for pattern in self.expand(files)?.split_whitespace() {
+            let paths = if is_glob(pattern) {
+                glob(pattern)
+            } else {
+                vec![pattern.to_string()]
+            };
+
+            for path in paths {
+                let (path, file) = match self.find_include(&path) {
+                    Ok(found) => found,
+                    Err(_) if optional => continue,
+                    Err(e) => return Err(MakeError::new(e, self.context.clone())),
+                };
+
+                let canonical = canonicalize(Path::new(&path));
+                if self.include_stack.iter().any(|(p, _)| *p == canonical) {
+                    let chain = self
+                        .include_stack
+                        .iter()
+                        .map(|(_, name)| name.as_str())
+                        .chain([path.as_str()])
+                        .collect::<Vec<_>>();
+                    return Err(MakeError::new(
+                        format!("Include loop: {}.", chain.join(" -> ")),
+                        self.context.clone(),
+                    ));
+                }
+                if !self.included.insert(canonical.clone()) {
+                    continue;
+                }
+                self.add_to_makefile_list(Path::new(&path));
+
+                // The included file has its own context and conditionals, so save ours.
+                self.include_stack.push((canonical, path.clone()));
+                let context = std::mem::replace(&mut self.context, PathBuf::from(path).into());
+                let conditionals = std::mem::take(&mut self.conditionals);
+                let result = self.parse(BufReader::new(file));
+                self.context = context;
+                self.conditionals = conditionals;
+                self.include_stack.pop();
+                result?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Open an included makefile. A relative path which isn't found in the current directory is
+    /// searched for in the directories given with `-I` (in order), and then in `INCLUDE_DIRS`.
+    fn find_include(&self, path: &str) -> Result<(String, File), String> {
+        let mut candidates = vec![path.to_string()];
+        if !paths::is_absolute(path) {
+            let dirs = self.args.include_dirs.iter().map(String::as_str);
+            candidates.extend(
+                dirs.chain(INCLUDE_DIRS.iter().copied())
+                    .map(|dir| format!("{}/{path}", dir.trim_end_matches(paths::is_separator))),
+            );
+        }
+
+        for candidate in &candidates {
+            match File::open(candidate) {
+                Ok(file) => return Ok((candidate.clone(), file)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+                Err(e) => return Err(format!("Could not read makefile `{candidate}` ({e}).")),
+            }
+        }
+
+        Err(format!(
+            "Could not find makefile `{path}` (searched {}).",
+            candidates
+                .iter()
+                .map(|c| format!("`{c}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+
+    /// Note that a makefile is about to be parsed, by appending it to `MAKEFILE_LIST` (so its last
+    /// word is the makefile currently being parsed). Like any makefile assignment, this is ignored
+    /// if `MAKEFILE_LIST` was set on the command line.
+    fn add_to_makefile_list(&mut self, path: &Path) {
+        let var = self.vars.get("MAKEFILE_LIST");
+        if !Origin::File.may_replace(var.origin) {
+            return;
+        }
+
+        let list = &var.value;
+        let path = path.to_string_lossy();
+        let list = if list.is_empty() {
+            path.into_owned()
+        } else {
+            format!("{list} {path}")
+        };
+        self.vars
+            .set("MAKEFILE_LIST".to_string(), list, false)
+            .expect("valid variable name");
+    }
+
+    /// Handle a `vpath` directive: `vpath PATTERN DIRS` adds search directories for files matching
+    /// the pattern, `vpath PATTERN` clears them, and a bare `vpath` clears all of them.
+    fn vpath(&mut self, args: &str) -> Result<(), MakeError> {
+        let args = self.expand(args)?;
+        let mut words = args.split_whitespace();
+
+        match words.next() {
+            None => self.vpaths.clear(),
+            Some(pattern) => {
+                let dirs = words
+                    .flat_map(|w| w.split(paths::LIST_SEPARATOR))
+                    .filter(|d| !d.is_empty())
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>();
+
+                if dirs.is_empty() {
+                    self.vpaths.retain(|(p, _)| p != pattern);
+                } else {
+                    self.vpaths.push((pattern.to_string(), dirs));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle an `export` (if `exported`) or `unexport` directive. With no arguments, this exports
+    /// (or stops exporting) every variable. Otherwise, it applies to the listed variables, and
+    /// `export` may also assign the variable (e.g., `export CFLAGS = -O2`).
+    fn export(&mut self, args: &str, exported: bool) -> Result<(), MakeError> {
+        if args.is_empty() {
+            self.export_all = exported;
+            return Ok(());
+        }
+
+        if exported {
+            if let Some((name, operator, value)) = assignment::parse(args) {
+                self.assign(name, operator, value, Origin::File)?;
+                let name = self.expand(name)?.trim().to_string();
+                self.exports.insert(name, true);
+                return Ok(());
+            }
+        }
+
+        for name in self.expand(args)?.split_whitespace() {
+            self.exports.insert(name.to_string(), exported);
+        }
+        Ok(())
+    }
+
+    /// Get the changes to make to the environment of a recipe's commands: the exported variables,
+    /// with their values expanded by `expander` (so the `target_vars` apply), and the unexported
+    /// ones without a value, which are removed in case they were inherited.
+    pub(crate) fn exported_env(
+        &self,
+        expander: &mut Expander,
+        target_vars: &[&Assignment],
+    ) -> Result<Vec<(String, Option<String>)>, String> {
+        let mut origins = self
+            .vars
+            .iter()
+            .map(|(name, var)| (name, var.origin))
+            .collect::<HashMap<_, _>>();
+        for assignment in target_vars {
+            origins.insert(&assignment.name, assignment.origin);
+        }
+
+        let mut env = vec![];
+        for (name, origin) in origins {
+            let export = match self.exports.get(name) {
+                Some(&export) => export,
                 None => {
+                    self.export_all
+                        && !matches!(origin, Origin::Default | Origin::Automatic)
+                        && is_env_name(name)
+                }
+            };
+
+            // Unchanged variables from the environment are inherited anyway.
+            if export && origin != Origin::Environment {
+                env.push((
+                    name.to_string(),
+                    Some(expander.expand(&format!("$({name})"))?),
+                ));
+            }
+        }
+
+        for (name, &export) in &self.exports {
+            if !export {
+                env.push((name.clone(), None));
+            }
+        }
+        Ok(env)
+    }
+
+    /// Search for a file which does not exist as named, first in the directories given by matching
+    /// `vpath` directives, and then in the directories listed in `VPATH` (separated by colons, or
+    /// semicolons on Windows, or whitespace). Returns the path where the file was found.
+    pub fn vpath_search(&self, file: &str) -> Option<String> {
+        if Path::new(file).is_absolute() {
+            return None;
+        }
+
+        let vpath = Expander::new(&self.vars)
+            .expand("$(VPATH)")
+            .unwrap_or_default();
+        let dirs = self
+            .vpaths
+            .iter()
+            .filter(|(pattern, _)| match_pattern(pattern, file).is_some())
+            .flat_map(|(_, dirs)| dirs.iter().map(|d| d.as_str()))
+            .chain(vpath.split(|ch: char| ch == paths::LIST_SEPARATOR || ch.is_whitespace()))
+            .filter(|d| !d.is_empty());
+
+        dirs.map(|dir| format!("{}/{file}", dir.trim_end_matches(paths::is_separator)))
+            .find(|path| Path::new(path).exists())
+    }
+
+    /// Whether lines are currently being parsed, rather than skipped by a conditional.
+    fn is_active(&self) -> bool {
+        self.conditionals.last().is_none_or(|c| c.active)
+    }
+
+    /// Update the conditional state for a directive. Conditions are only evaluated when their
+    /// branch could be taken, so expansions in skipped branches have no side effects.
+    fn handle_directive(&mut self, directive: Directive) -> Result<(), MakeError> {
+        match directive {
+            Directive::If(condition) => {
+                let parent_active = self.is_active();
+                let active = parent_active && self.evaluate(&condition)?;
+                self.conditionals.push(Conditional {
+                    parent_active,
+                    active,
+                    satisfied: active,
+                    seen_else: false,
+                    context: self.context.clone(),
+                });
+            }
+            Directive::Else(condition) => {
+                let (could_take, seen_else) = match self.conditionals.last() {
+                    None => return Err(MakeError::new("Extraneous 'else'.", self.context.clone())),
+                    Some(c) => (c.parent_active && !c.satisfied, c.seen_else),
+                };
+                if seen_else {
                     return Err(MakeError::new(
-                        "No target specified and no default target found.",
-                        Context::new(),
-                    ))
+                        "Only one 'else' per conditional.",
+                        self.context.clone(),
+                    ));
+                }
+
+                let seen_else = condition.is_none();
+                let active = match condition {
+                    None => could_take,
+                    Some(condition) => could_take && self.evaluate(&condition)?,
+                };
+
+                let conditional = self.conditionals.last_mut().expect("checked above");
+                conditional.active = active;
+                conditional.satisfied |= active;
+                conditional.seen_else = seen_else;
+            }
+            Directive::Endif => {
+                if self.conditionals.pop().is_none() {
+                    return Err(MakeError::new("Extraneous 'endif'.", self.context.clone()));
                 }
-                Some(t) => targets.push(t.clone()),
             }
         }
 
-        for target in targets {
-            self.rule_map.execute(self, &target)?;
+        Ok(())
+    }
+
+    /// Evaluate the condition of a conditional directive.
+    fn evaluate(&mut self, condition: &Condition) -> Result<bool, MakeError> {
+        Ok(match condition {
+            Condition::Eq { a, b, negate } => (self.expand(a)? == self.expand(b)?) != *negate,
+            Condition::Defined { name, negate } => {
+                let name = self.expand(name)?;
+                let defined = !self.vars.get(name.trim()).value.is_empty();
+                defined != *negate
+            }
+        })
+    }
+
+    /// Expand a string in the context of the line being parsed, applying any side effects of the
+    /// expansion (e.g., `.SHELLSTATUS` being set by `$(shell ...)`) to the makefile's variables.
+    /// Text from `$(eval ...)` is parsed once the expansion is complete.
+    fn expand(&mut self, s: &str) -> Result<String, MakeError> {
+        let mut expander = Expander::new(&self.vars)
+            .with_context(&self.context)
+            .with_warn_undefined(self.args.warn_undefined_variables);
+        let result = expander
+            .expand(s)
+            .map_err(|e| MakeError::new(e, self.context.clone()))?;
+        let shell_status = expander.local(".SHELLSTATUS").map(|v| v.value.clone());
+        let evals = expander.take_evals();
+
+        if let Some(status) = shell_status {
+            self.vars
+                .set(".SHELLSTATUS".to_string(), status, false)
+                .expect("valid variable name");
+        }
+
+        for text in evals {
+            self.eval(&text)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Parse the text from `$(eval ...)` as makefile syntax, in the context of the line where it
+    /// appears. Any rule (or conditional, or definition) must be complete within the text.
+    fn eval(&mut self, text: &str) -> Result<(), MakeError> {
+        if self.eval_depth >= MAX_EVAL_DEPTH {
+            return Err(MakeError::new(
+                format!("Evaluations nested too deeply (over {MAX_EVAL_DEPTH})."),
+                self.context.clone(),
+            ));
+        }
+
+        self.eval_depth += 1;
+        let current_rule = self.current_rule.take();
+        let conditionals = self.conditionals.len();
+        let result = self.parse_eval(text, conditionals);
+        self.current_rule = current_rule;
+        self.eval_depth -= 1;
+        result
+    }
+
+    /// Parse the lines of text for `eval`, given how many conditionals were already open.
+    fn parse_eval(&mut self, text: &str, conditionals: usize) -> Result<(), MakeError> {
+        self.parse_lines(text.lines().map(|line| Ok(line.to_string())), false)?;
+        if let Some(rule) = self.current_rule.take() {
+            self.insert_rule(rule)?;
+        }
+
+        if self.conditionals.len() > conditionals {
+            self.conditionals.truncate(conditionals);
+            return Err(MakeError::new("Missing 'endif'.", self.context.clone()));
+        }
+        if self.define.take().is_some() {
+            return Err(MakeError::new("Missing 'endef'.", self.context.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Handle a completely parsed rule, noting any special targets and adding it to the
+    /// `rule_map`. A static pattern rule is added as a separate rule for each target, with the
+    /// target's stem substituted into the prerequisites.
+    pub fn insert_rule(&mut self, rule: Rule) -> Result<(), MakeError> {
+        let Some(pattern) = &rule.static_pattern else {
+            return self.add_rule(rule);
+        };
+
+        for target in &rule.targets {
+            let Some(stem) = match_pattern(pattern, target) else {
+                return Err(MakeError::new(
+                    format!("Target '{target}' doesn't match the target pattern '{pattern}'."),
+                    rule.context.clone(),
+                ));
+            };
+            let substitute_all = |patterns: &[String]| {
+                patterns
+                    .iter()
+                    .map(|p| substitute(p, stem))
+                    .collect::<Vec<_>>()
+            };
+
+            self.add_rule(Rule {
+                targets: vec![target.clone()],
+                prerequisites: substitute_all(&rule.prerequisites),
+                order_only: substitute_all(&rule.order_only),
+                ..rule.clone()
+            })?;
         }
 
         Ok(())
     }
+
+    /// Add a single rule (see `insert_rule`) to the `rule_map`.
+    fn add_rule(&mut self, mut rule: Rule) -> Result<(), MakeError> {
+        // Suffix rules are translated into the equivalent pattern rules (e.g., `.c.o:` into
+        // `%.o: %.c`).
+        if let [target] = rule.targets.as_slice() {
+            if rule.prerequisites.is_empty() {
+                if let Some((from, to)) = self.suffix_rule(target) {
+                    rule.prerequisites = vec![format!("%{from}")];
+                    rule.targets = vec![format!("%{to}")];
+                }
+            }
+        }
+
+        // The first normal target becomes the default goal, unless one is already set (or until it
+        // is reset by setting `.DEFAULT_GOAL` to be empty).
+        if self.vars.get(".DEFAULT_GOAL").value.trim().is_empty() {
+            if let Some(target) = rule
+                .targets
+                .iter()
+                .find(|t| !t.starts_with('.') && !is_pattern(t))
+            {
+                self.vars
+                    .set(".DEFAULT_GOAL".to_string(), target.clone(), false)
+                    .expect("valid variable name");
+            }
+        }
+
+        // Record the prerequisites of special targets.
+        for target in rule.targets.iter() {
+            match target.as_str() {
+                ".PHONY" => self.phony.extend(rule.prerequisites.iter().cloned()),
+                ".IGNORE" => self.ignore.extend(&rule.prerequisites),
+                ".SILENT" => self.silent.extend(&rule.prerequisites),
+                ".DELETE_ON_ERROR" => self.delete_on_error = true,
+                ".ONESHELL" => self.one_shell = true,
+                ".SECONDEXPANSION" => self.second_expansion = true,
+                ".EXPORT_ALL_VARIABLES" => self.export_all = true,
+                ".NOTPARALLEL" => self.not_parallel.extend(&rule.prerequisites),
+                ".INTERMEDIATE" => self.intermediate.extend(rule.prerequisites.iter().cloned()),
+                ".SECONDARY" => self.secondary.extend(&rule.prerequisites),
+                ".SUFFIXES" => {
+                    if rule.prerequisites.is_empty() {
+                        self.suffixes.clear();
+                    } else {
+                        self.suffixes.extend(rule.prerequisites.iter().cloned());
+                    }
+
+                    // Keep `$(.SUFFIXES)` in sync with the suffixes which are actually known.
+                    self.vars
+                        .set(".SUFFIXES", &self.suffixes.join(" "), false)
+                        .map_err(|e| MakeError::new(e, self.context.clone()))?;
+                }
+                _ => (),
+            }
+        }
+
+        // Add the rule to the `rule_map`.
+        self.rule_map.insert(rule)
+    }
+
+    /// Split the target of a suffix rule (e.g., `.c.o`) into its two known suffixes, or return
+    /// `None` if the target is not a suffix rule.
+    fn suffix_rule<'t>(&self, target: &'t str) -> Option<(&'t str, &'t str)> {
+        if !target.starts_with('.') || target.contains(['/', '%']) {
+            return None;
+        }
+
+        self.suffixes.iter().find_map(|from| {
+            let to = target.strip_prefix(from.as_str())?;
+            self.suffixes
+                .iter()
+                .any(|s| s == to)
+                .then_some((&target[..from.len()], to))
+        })
+    }
+
+    /// Use `hash_fn` to hash the contents of prerequisites with `--check-hashes`, rather than the
+    /// default (64-bit FNV-1a). Since hashes are recorded across runs, changing the function may
+    /// cause targets to be remade once.
+    pub fn with_hash_fn(mut self, hash_fn: HashFn) -> Self {
+        self.hash_fn = hash_fn;
+        self
+    }
+
+    /// Principal interface for executing a parsed makefile, given a list of targets.
+    pub fn execute(&self) -> Result<(), MakeError> {
+        self.rule_map.execute(self, &self.goals()?)
+    }
+
+    /// Determine whether the targets are up to date, without executing anything.
+    pub fn question(&self) -> Result<bool, MakeError> {
+        self.rule_map.question(self, &self.goals()?)
+    }
+
+    /// Get the targets to make, which default to the default target if none were provided.
+    fn goals(&self) -> Result<Vec<String>, MakeError> {
+        if !self.args.targets.is_empty() {
+            return Ok(self.args.targets.clone());
+        }
+
+        // The default goal may be overridden by setting `.DEFAULT_GOAL` in the makefile.
+        let default_goal = Expander::new(&self.vars)
+            .expand("$(.DEFAULT_GOAL)")
+            .map_err(|e| MakeError::new(e, Context::new()))?
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        match default_goal.len() {
+            0 => Err(MakeError::new(
+                "No target specified and no default target found.",
+                Context::new(),
+            )),
+            1 => Ok(default_goal),
+            _ => Err(MakeError::new(
+                "The `.DEFAULT_GOAL` variable contains more than one target.",
+                Context::new(),
+            )),
+        }
+    }
+}
+
+/// Check whether `line` assigns the variable `name` (e.g., `include = main.mk`), rather than using
+/// the directive of the same name.
+fn is_assignment_to(line: &str, name: &str) -> bool {
+    assignment::parse(line).is_some_and(|(n, _, _)| n == name)
 }
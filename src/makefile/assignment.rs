@@ -0,0 +1,126 @@
+//! Parsing of variable assignments (e.g., `CC := gcc`). This module only deals with the syntax; the
+//! parser applies the assignment, since that may require expanding the value.
+
+use crate::vars::Origin;
+
+/// The assignment operators, which determine when the value is expanded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    /// `=`: The value is stored unexpanded, and expanded each time the variable is used.
+    Recursive,
+    /// `:=` (or `::=`): The value is expanded once, when the variable is defined.
+    Simple,
+    /// `?=`: Like `=`, but only if the variable is not already defined.
+    Conditional,
+    /// `+=`: The value is appended, keeping the flavor of the existing variable.
+    Append,
+}
+
+/// An assignment which is applied later, such as a target-specific variable (e.g., `debug: CFLAGS
+/// += -g`). The name is already expanded, as is the value of a simple assignment. The origin is
+/// `Origin::Override` for assignments using the `override` directive.
+#[derive(Clone, Debug)]
+pub struct Assignment {
+    pub name: String,
+    pub operator: Operator,
+    pub value: String,
+    pub origin: Origin,
+}
+
+/// Parse a line as a variable assignment, returning the (unexpanded) name, the operator, and the
+/// (unexpanded) value, or `None` if the line is not an assignment. Whichever of `:` or `=` comes
+/// first (outside of any variable references) decides, so `a: b = c` is not an assignment.
+pub fn parse(line: &str) -> Option<(&str, Operator, &str)> {
+    let mut depth: i32 = 0;
+
+    for (i, ch) in line.char_indices() {
+        match ch {
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth -= 1,
+            ':' if depth == 0 => {
+                let rest = &line[i + 1..];
+                let value = rest.strip_prefix('=').or_else(|| rest.strip_prefix(":="))?;
+                return Some((line[..i].trim(), Operator::Simple, value.trim_start()));
+            }
+            '=' if depth == 0 => {
+                let name = &line[..i];
+                let (name, operator) = if let Some(name) = name.strip_suffix('+') {
+                    (name, Operator::Append)
+                } else if let Some(name) = name.strip_suffix('?') {
+                    (name, Operator::Conditional)
+                } else {
+                    (name, Operator::Recursive)
+                };
+
+                return Some((name.trim(), operator, line[i + 1..].trim_start()));
+            }
+            _ => (),
+        }
+    }
+
+    None
+}
+
+/// Parse the header of a `define` directive (e.g., `NAME :=`), returning the (unexpanded) name and
+/// the operator, which is recursive if none is given.
+pub fn parse_define(header: &str) -> Result<(&str, Operator), String> {
+    let (name, operator) = match parse(header) {
+        None => (header.trim(), Operator::Recursive),
+        Some((name, operator, "")) => (name, operator),
+        Some(_) => return Err("Extraneous text after 'define' directive.".to_string()),
+    };
+
+    if name.is_empty() {
+        return Err("Empty variable name in 'define'.".to_string());
+    }
+    Ok((name, operator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operators() {
+        assert_eq!(parse("A = b c"), Some(("A", Operator::Recursive, "b c")));
+        assert_eq!(parse("A=b"), Some(("A", Operator::Recursive, "b")));
+        assert_eq!(parse("A := $(B)"), Some(("A", Operator::Simple, "$(B)")));
+        assert_eq!(parse("A ::= b"), Some(("A", Operator::Simple, "b")));
+        assert_eq!(parse("A ?= b"), Some(("A", Operator::Conditional, "b")));
+        assert_eq!(parse("A += b"), Some(("A", Operator::Append, "b")));
+        assert_eq!(parse("A ="), Some(("A", Operator::Recursive, "")));
+    }
+
+    #[test]
+    fn test_parse_define() {
+        assert_eq!(parse_define(" A "), Ok(("A", Operator::Recursive)));
+        assert_eq!(parse_define("A ="), Ok(("A", Operator::Recursive)));
+        assert_eq!(parse_define("A:="), Ok(("A", Operator::Simple)));
+        assert_eq!(parse_define("A ::= "), Ok(("A", Operator::Simple)));
+        assert_eq!(parse_define("A +="), Ok(("A", Operator::Append)));
+        assert_eq!(parse_define("A ?="), Ok(("A", Operator::Conditional)));
+        assert!(parse_define("").is_err());
+        assert!(parse_define("A = b").is_err());
+    }
+
+    #[test]
+    fn test_not_assignments() {
+        assert_eq!(parse("target: prereq"), None);
+        assert_eq!(parse("target: A = b"), None);
+        assert_eq!(parse("target:: prereq"), None);
+        assert_eq!(parse("echo hello"), None);
+    }
+
+    #[test]
+    fn test_references_in_name_and_value() {
+        assert_eq!(
+            parse("$(X:a=b) = c"),
+            Some(("$(X:a=b)", Operator::Recursive, "c"))
+        );
+        assert_eq!(
+            parse("URL := http://example.com"),
+            Some(("URL", Operator::Simple, "http://example.com"))
+        );
+        assert_eq!(parse("A = b:c"), Some(("A", Operator::Recursive, "b:c")));
+    }
+}
@@ -0,0 +1,189 @@
+//! Parsing of the conditional directives (`ifeq`, `ifneq`, `ifdef`, `ifndef`, `else`, and
+//! `endif`). This module only deals with the syntax; conditions are evaluated by the parser, since
+//! that requires expanding the arguments.
+
+use crate::context::Context;
+
+/// A condition from an `ifeq`, `ifneq`, `ifdef`, or `ifndef` directive, with its arguments still
+/// unexpanded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Condition<'a> {
+    Eq {
+        a: &'a str,
+        b: &'a str,
+        negate: bool,
+    },
+    Defined {
+        name: &'a str,
+        negate: bool,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Directive<'a> {
+    If(Condition<'a>),
+    /// An `else`, optionally followed by another condition (e.g., `else ifdef FOO`).
+    Else(Option<Condition<'a>>),
+    Endif,
+}
+
+/// The state of an open conditional block.
+#[derive(Debug)]
+pub struct Conditional {
+    /// Whether the enclosing block (if any) is active.
+    pub parent_active: bool,
+    /// Whether lines in the current branch should be parsed.
+    pub active: bool,
+    /// Whether any branch has been taken yet, in which case later branches are skipped.
+    pub satisfied: bool,
+    /// Whether a plain `else` has been seen, after which no more branches are allowed.
+    pub seen_else: bool,
+    /// Where the block was opened, for reporting a missing `endif`.
+    pub context: Context,
+}
+
+/// Parse a line as a conditional directive, returning `None` if it is not one.
+pub fn parse_directive(line: &str) -> Option<Result<Directive<'_>, String>> {
+    let line = strip_comment(line).trim();
+    let (keyword, rest) = split_keyword(line);
+
+    match keyword {
+        "else" => Some(if rest.is_empty() {
+            Ok(Directive::Else(None))
+        } else {
+            let (keyword, rest) = split_keyword(rest);
+            match parse_condition(keyword, rest) {
+                Some(condition) => condition.map(|c| Directive::Else(Some(c))),
+                None => Err("Extraneous text after 'else' directive.".to_string()),
+            }
+        }),
+        "endif" => Some(if rest.is_empty() {
+            Ok(Directive::Endif)
+        } else {
+            Err("Extraneous text after 'endif' directive.".to_string())
+        }),
+        _ => parse_condition(keyword, rest).map(|c| c.map(Directive::If)),
+    }
+}
+
+/// Parse the condition of an `if...` directive, returning `None` if the keyword is not one.
+fn parse_condition<'a>(keyword: &str, args: &'a str) -> Option<Result<Condition<'a>, String>> {
+    let negate = match keyword {
+        "ifeq" | "ifdef" => false,
+        "ifneq" | "ifndef" => true,
+        _ => return None,
+    };
+
+    Some(if keyword.ends_with("def") {
+        if args.is_empty() {
+            Err(format!("Missing variable name for '{keyword}'."))
+        } else {
+            Ok(Condition::Defined { name: args, negate })
+        }
+    } else {
+        parse_eq_args(args)
+            .map(|(a, b)| Condition::Eq { a, b, negate })
+            .ok_or_else(|| format!("Invalid syntax in '{keyword}' directive."))
+    })
+}
+
+/// Parse the arguments of `ifeq`/`ifneq`, which are either `(a,b)` or two quoted strings (e.g.,
+/// `"a" 'b'`).
+fn parse_eq_args(args: &str) -> Option<(&str, &str)> {
+    if let Some(inner) = args.strip_prefix('(') {
+        let inner = inner.strip_suffix(')')?;
+
+        // Find the top-level comma, ignoring any inside variable references.
+        let mut depth = 0;
+        for (i, ch) in inner.char_indices() {
+            match ch {
+                '(' | '{' => depth += 1,
+                ')' | '}' => depth -= 1,
+                ',' if depth == 0 => return Some((inner[..i].trim(), inner[i + 1..].trim())),
+                _ => (),
+            }
+        }
+
+        return None;
+    }
+
+    let (a, rest) = parse_quoted(args)?;
+    let (b, rest) = parse_quoted(rest.trim_start())?;
+    rest.trim().is_empty().then_some((a, b))
+}
+
+/// Parse a string quoted with `"` or `'`, returning the contents and the remainder.
+fn parse_quoted(s: &str) -> Option<(&str, &str)> {
+    let quote = s.chars().next().filter(|ch| *ch == '"' || *ch == '\'')?;
+    let (contents, rest) = s[1..].split_once(quote)?;
+    Some((contents, rest))
+}
+
+/// Split the leading keyword from a directive line. The keyword may be followed directly by `(`.
+fn split_keyword(line: &str) -> (&str, &str) {
+    let end = line
+        .find(|ch: char| ch.is_whitespace() || ch == '(')
+        .unwrap_or(line.len());
+    (&line[..end], line[end..].trim())
+}
+
+/// Strip a trailing comment from a directive line.
+fn strip_comment(line: &str) -> &str {
+    line.split_once('#').map_or(line, |(line, _)| line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_eq() {
+        assert_eq!(
+            parse_directive("ifeq ($(OS),Windows_NT)"),
+            Some(Ok(Directive::If(Condition::Eq {
+                a: "$(OS)",
+                b: "Windows_NT",
+                negate: false
+            })))
+        );
+        assert_eq!(
+            parse_directive("ifneq \"$(call f,a)\" 'b' # comment"),
+            Some(Ok(Directive::If(Condition::Eq {
+                a: "$(call f,a)",
+                b: "b",
+                negate: true
+            })))
+        );
+        assert_eq!(
+            parse_directive("ifeq ($(subst a,b,c), )"),
+            Some(Ok(Directive::If(Condition::Eq {
+                a: "$(subst a,b,c)",
+                b: "",
+                negate: false
+            })))
+        );
+        assert!(matches!(parse_directive("ifeq (a b)"), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_parse_other_directives() {
+        assert_eq!(
+            parse_directive("ifndef FOO"),
+            Some(Ok(Directive::If(Condition::Defined {
+                name: "FOO",
+                negate: true
+            })))
+        );
+        assert_eq!(
+            parse_directive("else ifdef BAR"),
+            Some(Ok(Directive::Else(Some(Condition::Defined {
+                name: "BAR",
+                negate: false
+            }))))
+        );
+        assert_eq!(parse_directive("  else"), Some(Ok(Directive::Else(None))));
+        assert_eq!(parse_directive("endif"), Some(Ok(Directive::Endif)));
+        assert_eq!(parse_directive("ifeqx = 1"), None);
+        assert_eq!(parse_directive("target: ifeq"), None);
+    }
+}
@@ -0,0 +1,104 @@
+//! Printing of the data base (`--print-data-base`), which shows the variables and rules of a
+//! parsed makefile, to help diagnose why it behaves as it does. The format is similar to that of
+//! GNU make: variables and rules are written in makefile syntax, with comments describing them.
+
+use std::fmt::Write;
+
+use super::pattern::is_pattern;
+use super::{Makefile, Rule};
+
+impl Makefile {
+    /// Describe the variables (sorted by name, and with their origins), the default goal, and the
+    /// rules (in definition order, with pattern rules listed separately).
+    pub fn data_base(&self) -> String {
+        let mut out = String::from("# Variables\n\n");
+
+        let mut vars = self.vars.iter().collect::<Vec<_>>();
+        vars.sort_by_key(|(name, _)| *name);
+        for (name, var) in vars {
+            let _ = writeln!(out, "# {}", var.origin.as_str());
+            let operator = if var.recursive { "=" } else { ":=" };
+            if var.value.contains('\n') {
+                let _ = writeln!(out, "define {name} {operator}\n{}\nendef", var.value);
+            } else {
+                let _ = writeln!(out, "{name} {operator} {}", var.value);
+            }
+        }
+
+        let default_goal = &self.vars.get(".DEFAULT_GOAL").value;
+        let _ = writeln!(out, "\n# Default goal: {default_goal}");
+
+        let (pattern_rules, rules): (Vec<_>, Vec<_>) = self
+            .rule_map
+            .rules()
+            .partition(|rule| rule.targets.iter().any(|t| is_pattern(t)));
+        for (heading, rules) in [("Rules", rules), ("Pattern rules", pattern_rules)] {
+            let _ = writeln!(out, "\n# {heading}");
+            for rule in rules {
+                out.push('\n');
+                describe_rule(&mut out, rule);
+            }
+        }
+
+        out
+    }
+}
+
+/// Write a rule in makefile syntax, with a comment saying where it was defined.
+fn describe_rule(out: &mut String, rule: &Rule) {
+    let colon = if rule.double_colon { "::" } else { ":" };
+    let _ = write!(out, "{}{colon}", rule.targets.join(" "));
+    for prerequisite in &rule.prerequisites {
+        let _ = write!(out, " {prerequisite}");
+    }
+    if !rule.order_only.is_empty() {
+        let _ = write!(out, " | {}", rule.order_only.join(" "));
+    }
+    if rule.empty_recipe {
+        out.push_str(" ;");
+    }
+    out.push('\n');
+
+    if let Some(path) = &rule.context.path {
+        let _ = writeln!(
+            out,
+            "#  Defined at {}:{}.",
+            path.display(),
+            rule.context.line_number
+        );
+    }
+    for line in &rule.recipe {
+        let _ = writeln!(out, "\t{line}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Args, Env, Makefile};
+
+    #[test]
+    fn test_data_base() {
+        let input =
+            "X = $(Y)\nY := 1\n%.o: %.c\n\t$(CC) -c $<\nall: x.o | dir\n\t@echo hi\ndir: ;\n";
+        let makefile = Makefile::from_reader(
+            "inline.mk".into(),
+            input.as_bytes(),
+            Args::default(),
+            Env::new(),
+        )
+        .unwrap();
+
+        let data_base = makefile.data_base();
+        assert!(data_base.starts_with("# Variables\n\n"));
+        assert!(data_base.contains("\n# default\nCC := cc\n"));
+        assert!(data_base.contains("\n# file\nX = $(Y)\n# file\nY := 1\n"));
+        assert!(data_base.contains("\n# Default goal: all\n"));
+        assert!(data_base.ends_with(
+            "\n# Rules\n\n\
+             all: x.o | dir\n#  Defined at inline.mk:5.\n\t@echo hi\n\n\
+             dir: ;\n#  Defined at inline.mk:7.\n\n\
+             # Pattern rules\n\n\
+             %.o: %.c\n#  Defined at inline.mk:3.\n\t$(CC) -c $<\n"
+        ));
+    }
+}
@@ -1,111 +1,291 @@
-use crate::vars::Vars;
-
-/// Represents a frame on the stack inside the `expand` function. This is used for tracking the
-/// previous buffer when expanding potentially nested expressions (i.e., either `$()` or `${}`).
-/// Single variable expansions (e.g., `$X`) are handled inline without creating a frame since they
-/// cannot possibly have nested expressions.
-struct Frame {
-    pub previous_buffer: String,
-    /// Track which character opened this stack frame (should be parenthesis or brace).
-    pub opening_delimiter: char,
+mod functions;
+
+use std::collections::HashMap;
+
+use super::assignment::{Assignment, Operator};
+use crate::context::Context;
+use crate::error::log_warn;
+use crate::vars::{Origin, Var, Vars};
+
+/// Find the index of the delimiter which closes an expression, given the remainder of the input
+/// following the opening delimiter. Only delimiters of the same type are counted for nesting,
+/// matching the behavior of other make implementations.
+fn find_closing(s: &str, opening_delimiter: char) -> Option<usize> {
+    let closing_delimiter = if opening_delimiter == '(' { ')' } else { '}' };
+    let mut depth = 0;
+
+    for (i, c) in s.char_indices() {
+        if c == opening_delimiter {
+            depth += 1;
+        } else if c == closing_delimiter {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        }
+    }
+
+    None
 }
 
-/// The primary public interface for running variable expansion on an input string, given a
-/// collection of `vars`.
-///
-/// The goal here is to be `O(n)`. This works by iterating over the input string and storing plain
-/// characters into a buffer until we hit either:
-///  1. A simple variable expansion (e.g., `$X`), where we just evaluate it against `vars` inline
-///     since there could not possibly be any nesting.
-///  2. A long variable expansion (e.g., `$(` or `${`), where there could be nested
-///     expressions, where we push the current buffer onto a stack, and then continue parsing. When
-///     we hit a matching closing delimiter (tracked on the stack frame), we evaluate the buffer,
-///     pop the previous buffer off the stack, join it with the evaluated value, and keep going.
-pub fn expand(s: &str, vars: &Vars) -> Result<String, String> {
-    let mut stack: Vec<Frame> = vec![];
-    let mut current_buffer: String = String::with_capacity(s.len());
-    let mut hit_variable: bool = false;
-
-    for c in s.chars() {
+/// Split the raw (unexpanded) arguments of a function call on commas, into at most `max` arguments.
+/// Commas nested inside parentheses or braces (e.g., inside a variable reference) do not separate
+/// arguments, and any commas beyond the `max`-th argument are part of the last argument.
+fn split_args(s: &str, max: usize) -> Vec<&str> {
+    let mut args = vec![];
+    let mut depth: i32 = 0;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
         match c {
-            '$' => {
-                hit_variable = !hit_variable;
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth -= 1,
+            ',' if depth == 0 && args.len() + 1 < max => {
+                args.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    args.push(&s[start..]);
+    args
+}
+
+/// Check whether `name` is a variable which make itself may define, so that expanding it while it
+/// is undefined is not a mistake: the automatic variables (e.g., `$@` or `$(<D)`), the parameters
+/// of `$(call ...)` (e.g., `$(2)`), special variables (e.g., `.SHELLFLAGS`), and the variables
+/// which affect how make works when set (e.g., `VPATH`).
+fn is_builtin(name: &str) -> bool {
+    const AUTOMATIC: &[char] = &['@', '%', '<', '?', '^', '+', '|', '*'];
+    const SPECIAL: &[&str] = &["GPATH", "MAKEFILES", "MAKESHELL", "VPATH"];
+
+    let mut chars = name.chars();
+    let automatic = match (chars.next(), chars.next(), chars.next()) {
+        (Some(ch), None, None) => AUTOMATIC.contains(&ch),
+        (Some(ch), Some('D' | 'F'), None) => AUTOMATIC.contains(&ch),
+        _ => false,
+    };
+
+    automatic
+        || name.chars().all(|ch| ch.is_ascii_digit())
+        || name.starts_with('.')
+        || SPECIAL.contains(&name)
+}
+
+/// Performs variable expansion against a collection of `vars`, optionally shadowed by some local
+/// variables (e.g., automatic variables such as `$@` while executing a recipe).
+pub struct Expander<'a> {
+    vars: &'a Vars,
+    locals: HashMap<String, Var>,
+
+    /// The recursive variables currently being expanded, to detect self-references.
+    expanding: Vec<String>,
+
+    /// How deeply `$(call ...)` is nested, to stop runaway recursion.
+    calls: usize,
+
+    /// Text from `$(eval ...)`, which is parsed (by the caller) once the expansion is complete.
+    evals: Vec<String>,
+
+    /// Where the expansion happens, for messages from functions like `$(warning ...)`.
+    context: Option<&'a Context>,
+
+    /// Whether to warn when an undefined variable is expanded (see `--warn-undefined-variables`).
+    warn_undefined: bool,
+}
+
+impl<'a> Expander<'a> {
+    pub fn new(vars: &'a Vars) -> Self {
+        Self {
+            vars,
+            locals: HashMap::new(),
+            expanding: vec![],
+            calls: 0,
+            evals: vec![],
+            context: None,
+            warn_undefined: false,
+        }
+    }
+
+    /// Set the context in which the expansion happens (e.g., the makefile line being parsed).
+    pub fn with_context(mut self, context: &'a Context) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Warn (or not) whenever an undefined variable is expanded, except for those which make
+    /// defines itself (see `is_builtin`).
+    pub fn with_warn_undefined(mut self, warn_undefined: bool) -> Self {
+        self.warn_undefined = warn_undefined;
+        self
+    }
+
+    /// Set a local (simply-expanded) variable, which shadows any variable of the same name. Locals
+    /// are set for automatic variables (and the like), so they have an automatic origin.
+    pub fn set_local(&mut self, k: impl Into<String>, v: impl Into<String>) {
+        self.locals.insert(
+            k.into(),
+            Var {
+                value: v.into(),
+                recursive: false,
+                origin: Origin::Automatic,
+            },
+        );
+    }
+
+    /// Take the text from any `$(eval ...)` calls, in the order they were expanded.
+    pub fn take_evals(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.evals)
+    }
+
+    /// Get a local variable, if it is set.
+    pub fn local(&self, k: &str) -> Option<&Var> {
+        self.locals.get(k)
+    }
+
+    /// Apply an assignment to the locals, in the context of the variables visible to this expander.
+    /// This is how target-specific variables are layered on top of the global variables.
+    pub fn assign(&mut self, assignment: &Assignment) -> Result<(), String> {
+        let Assignment {
+            name,
+            operator,
+            value,
+            origin,
+        } = assignment;
+        // Variables from the command line take precedence, even over target-specific ones, unless
+        // the `override` directive is used.
+        if !origin.may_replace(self.get(name).origin) {
+            return Ok(());
+        }
+        let defined = self.locals.contains_key(name) || self.vars.is_defined(name);
+
+        let (value, recursive) = match operator {
+            Operator::Recursive => (value.clone(), true),
+            // Simple values were already expanded when they were defined.
+            Operator::Simple => (value.clone(), false),
+            Operator::Conditional if defined => return Ok(()),
+            Operator::Conditional => (value.clone(), true),
+            Operator::Append if defined => {
+                let current = self.get(name);
+                let (old, recursive) = (current.value.clone(), current.recursive);
+                let value = if recursive {
+                    value.clone()
+                } else {
+                    self.expand(value)?
+                };
 
-                // Push a literal `$` if it's the second one (`hit_variable` is `false`)
-                if !hit_variable {
-                    current_buffer.push(c);
+                if old.is_empty() {
+                    (value, recursive)
+                } else {
+                    (format!("{old} {value}"), recursive)
                 }
             }
-            '(' | '{' => {
-                // If we haven't hit a variable, consider this a normal char.
-                if !hit_variable {
-                    current_buffer.push(c);
-                    continue;
-                }
+            Operator::Append => (value.clone(), true),
+        };
+        let var = Var {
+            value,
+            recursive,
+            origin: *origin,
+        };
 
-                // Otherwise, push a frame onto the stack to begin processing this expression.
-                stack.push(Frame {
-                    previous_buffer: current_buffer,
-                    opening_delimiter: c,
-                });
-                current_buffer = "".to_string();
-                hit_variable = false;
-            }
-            ')' | '}' => {
-                match stack.last() {
-                    None => current_buffer.push(c),
-                    Some(f) => {
-                        // Test if this character matches the opening delimiter.
-                        if (c == '}' && f.opening_delimiter == '{')
-                            || (c == ')' && f.opening_delimiter == '(')
-                        {
-                            // Expression terminated, so expand.
-                            let var = vars.get(&current_buffer);
-                            let recursive_result: String;
-
-                            // Handle recursive variable expansion.
-                            let result = if var.recursive {
-                                recursive_result = expand(var.value.as_str(), vars)?;
-                                &recursive_result
-                            } else {
-                                &var.value
-                            };
-
-                            // This `unwrap()` is safe because we checked that the stack contains a
-                            // `last()` element, so it cannot be empty.
-                            current_buffer = stack.pop().unwrap().previous_buffer;
-                            current_buffer.push_str(result);
-                            hit_variable = false;
-                            continue;
-                        }
-
-                        // Not the right trailing delimiter, so consider it just a char.
-                        current_buffer.push(c)
-                    }
+        self.locals.insert(name.clone(), var);
+        Ok(())
+    }
+
+    /// Get a variable, preferring locals.
+    fn get(&self, k: &str) -> &Var {
+        self.locals
+            .get(k.trim())
+            .unwrap_or_else(|| self.vars.get(k))
+    }
+
+    /// The primary public interface for running variable expansion on an input string.
+    ///
+    /// This works by copying plain text into a buffer until we hit a `$`, at which point we have
+    /// either:
+    ///  1. An escaped dollar sign (`$$`), which becomes a literal `$`.
+    ///  2. A simple variable expansion (e.g., `$X`), where we just evaluate it inline since there
+    ///     could not possibly be any nesting.
+    ///  3. A long expansion (e.g., `$(...)` or `${...}`), where we find the matching closing
+    ///     delimiter and hand the contents to `expand_expression`, which may recursively expand
+    ///     nested expressions. Expanding the contents lazily (rather than inside-out) is what
+    ///     allows functions like `foreach` or `if` to control if and when their arguments are
+    ///     expanded.
+    pub fn expand(&mut self, s: &str) -> Result<String, String> {
+        let mut result = String::with_capacity(s.len());
+        let mut rest = s;
+
+        while let Some(i) = rest.find('$') {
+            result.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+
+            let Some(c) = rest.chars().next() else {
+                break;
+            };
+
+            match c {
+                '$' => {
+                    result.push('$');
+                    rest = &rest[1..];
                 }
-            }
-            _ => {
-                // If we hit the variable indicator, then inline expansion since nesting is impossible.
-                if hit_variable {
-                    let eval = &vars.get(c.to_string()).value;
-                    current_buffer.push_str(eval);
-                    hit_variable = false;
-                    continue;
+                '(' | '{' => {
+                    let end = find_closing(&rest[1..], c)
+                        .ok_or_else(|| format!("Unclosed variable: {rest}"))?;
+                    result.push_str(&self.expand_expression(&rest[1..end + 1])?);
+                    rest = &rest[end + 2..];
+                }
+                _ => {
+                    result.push_str(&self.expand_variable(&c.to_string())?);
+                    rest = &rest[c.len_utf8()..];
                 }
+            }
+        }
 
-                // Otherwise, just push the char.
-                current_buffer.push(c);
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Expand the contents of a long expression, which is either a function call (e.g.,
+    /// `$(wildcard *.c)`) or a variable reference, whose name may itself contain references.
+    fn expand_expression(&mut self, s: &str) -> Result<String, String> {
+        if let Some((name, args)) = s.split_once([' ', '\t']) {
+            if let Some(max_args) = functions::max_args(name) {
+                let args = split_args(args.trim_start(), max_args);
+                return functions::call(self, name, &args);
             }
         }
+
+        let name = self.expand(s)?;
+        self.expand_variable(&name)
     }
 
-    // Return current buffer if the stack is empty, else an error.
-    match stack.pop() {
-        None => Ok(current_buffer),
-        Some(frame) => Err(format!(
-            "Unclosed variable: {}{}",
-            frame.opening_delimiter, frame.previous_buffer
-        )),
+    /// Expand a variable by name, handling recursive variables.
+    fn expand_variable(&mut self, name: &str) -> Result<String, String> {
+        let var = self.get(name);
+        if self.warn_undefined && var.origin == Origin::Undefined && !is_builtin(name.trim()) {
+            log_warn(
+                format!("Undefined variable '{}'.", name.trim()),
+                self.context,
+            );
+        }
+
+        if var.recursive {
+            let value = var.value.clone();
+            let name = name.trim();
+            if self.expanding.iter().any(|n| n == name) {
+                return Err(format!(
+                    "Recursive variable '{name}' references itself (eventually)."
+                ));
+            }
+
+            self.expanding.push(name.to_string());
+            let result = self.expand(&value);
+            self.expanding.pop();
+            result
+        } else {
+            Ok(var.value.clone())
+        }
     }
 }
 
@@ -113,6 +293,21 @@ pub fn expand(s: &str, vars: &Vars) -> Result<String, String> {
 mod tests {
     use super::*;
 
+    /// Expand an input string without any local variables.
+    fn expand(s: &str, vars: &Vars) -> Result<String, String> {
+        Expander::new(vars).expand(s)
+    }
+
+    #[test]
+    fn test_is_builtin() {
+        for name in ["@", "<D", "^F", "*", "1", "10", ".SHELLFLAGS", "VPATH"] {
+            assert!(is_builtin(name), "{name}");
+        }
+        for name in ["CFLAGS", "A", "@DF", "<X", "1a"] {
+            assert!(!is_builtin(name), "{name}");
+        }
+    }
+
     #[test]
     fn test_basic_single_letter_expansions() {
         let vars = Vars::new([("A", "VALUE A"), ("B", "VALUE B")]);
@@ -227,4 +422,50 @@ mod tests {
         let vars = Vars::new([("TEST", "Value")]);
         assert!(expand("${TEST", &vars).is_err());
     }
+
+    #[test]
+    fn test_locals_shadow_vars() {
+        let vars = Vars::new([("A", "global"), ("B", "b")]);
+        let mut expander = Expander::new(&vars);
+        expander.set_local("A", "local");
+        expander.set_local("@", "target");
+        assert_eq!(
+            expander.expand("$A $(B) $@ $$@").unwrap(),
+            "local b target $@"
+        );
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(split_args("a,b,c", 3), vec!["a", "b", "c"]);
+        assert_eq!(split_args("a,b,c", 2), vec!["a", "b,c"]);
+        assert_eq!(split_args("$(X,Y),${Z,W}", 2), vec!["$(X,Y)", "${Z,W}"]);
+        assert_eq!(split_args("", 2), vec![""]);
+    }
+
+    #[test]
+    fn test_assign() {
+        let vars = Vars::new([("CFLAGS", "-O2"), ("X", "x")]);
+        let mut expander = Expander::new(&vars);
+        let assign = |expander: &mut Expander, name: &str, operator, value: &str| {
+            expander
+                .assign(&Assignment {
+                    name: name.to_string(),
+                    operator,
+                    value: value.to_string(),
+                    origin: Origin::File,
+                })
+                .unwrap()
+        };
+
+        // `Vars::new` defines simple variables, so appending expands immediately.
+        assign(&mut expander, "CFLAGS", Operator::Append, "-g $(X)");
+        assign(&mut expander, "X", Operator::Conditional, "ignored");
+        assign(&mut expander, "NEW", Operator::Conditional, "$(X)");
+        assign(&mut expander, "X", Operator::Recursive, "y");
+        assert_eq!(expander.expand("$(CFLAGS) $(NEW)").unwrap(), "-O2 -g x y");
+
+        // The global variables are unchanged.
+        assert_eq!(expand("$(CFLAGS) $(NEW)", &vars).unwrap(), "-O2 ");
+    }
 }
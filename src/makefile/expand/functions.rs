@@ -0,0 +1,1125 @@
+//! Implementations of the builtin functions (e.g., `$(wildcard ...)`). Functions receive their
+//! arguments unexpanded, so each function is responsible for expanding its arguments (if and when
+//! appropriate) using the provided `Expander`.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::iter::Peekable;
+use std::path::Path;
+use std::process::Stdio;
+use std::vec;
+
+use super::Expander;
+use crate::error::log_warn;
+use crate::glob::{glob, is_glob};
+use crate::makefile::pattern::{match_pattern, substitute};
+use crate::makefile::shell;
+use crate::paths;
+use crate::vars::{Origin, Var};
+
+/// How deeply `$(call ...)` may be nested, which stops runaway recursion before the stack
+/// overflows.
+const MAX_CALL_DEPTH: usize = 200;
+
+/// The builtin functions, along with the maximum number of arguments each one accepts. Any commas
+/// beyond the maximum are part of the last argument.
+const FUNCTIONS: [(&str, usize); 37] = [
+    ("abspath", 1),
+    ("addprefix", 2),
+    ("addsuffix", 2),
+    ("and", usize::MAX),
+    ("basename", 1),
+    ("call", usize::MAX),
+    ("dir", 1),
+    ("error", 1),
+    ("eval", 1),
+    ("expr", 1),
+    ("file", 2),
+    ("filter", 2),
+    ("filter-out", 2),
+    ("findstring", 2),
+    ("firstword", 1),
+    ("flavor", 1),
+    ("foreach", 3),
+    ("if", 3),
+    ("info", 1),
+    ("join", 2),
+    ("lastword", 1),
+    ("notdir", 1),
+    ("or", usize::MAX),
+    ("origin", 1),
+    ("patsubst", 3),
+    ("realpath", 1),
+    ("shell", 1),
+    ("sort", 1),
+    ("strip", 1),
+    ("subst", 3),
+    ("suffix", 1),
+    ("value", 1),
+    ("warning", 1),
+    ("wildcard", 1),
+    ("word", 2),
+    ("wordlist", 3),
+    ("words", 1),
+];
+
+/// Get the maximum number of arguments for a function, or `None` if there is no such function.
+pub fn max_args(name: &str) -> Option<usize> {
+    FUNCTIONS
+        .iter()
+        .find(|(function, _)| *function == name)
+        .map(|(_, max)| *max)
+}
+
+/// Call a builtin function with raw (unexpanded) arguments.
+pub fn call(expander: &mut Expander, name: &str, args: &[&str]) -> Result<String, String> {
+    match name {
+        "abspath" => abspath(expander, args),
+        "addprefix" => addprefix(expander, args),
+        "addsuffix" => addsuffix(expander, args),
+        "and" => and(expander, args),
+        "basename" => basename(expander, args),
+        "call" => call_(expander, args),
+        "dir" => dir(expander, args),
+        "error" => error(expander, args),
+        "eval" => eval(expander, args),
+        "expr" => expr(expander, args),
+        "file" => file(expander, args),
+        "filter" => filter(expander, args, true),
+        "filter-out" => filter(expander, args, false),
+        "findstring" => findstring(expander, args),
+        "firstword" => firstword(expander, args),
+        "flavor" => flavor(expander, args),
+        "foreach" => foreach(expander, args),
+        "if" => if_(expander, args),
+        "info" => info(expander, args),
+        "join" => join(expander, args),
+        "lastword" => lastword(expander, args),
+        "notdir" => notdir(expander, args),
+        "or" => or(expander, args),
+        "origin" => origin(expander, args),
+        "patsubst" => patsubst(expander, args),
+        "realpath" => realpath(expander, args),
+        "shell" => shell(expander, args),
+        "sort" => sort(expander, args),
+        "strip" => strip(expander, args),
+        "subst" => subst(expander, args),
+        "suffix" => suffix(expander, args),
+        "value" => value(expander, args),
+        "warning" => warning(expander, args),
+        "wildcard" => wildcard(expander, args),
+        "word" => word(expander, args),
+        "wordlist" => wordlist(expander, args),
+        "words" => words_(expander, args),
+        _ => unreachable!("unknown function `{name}`"),
+    }
+}
+
+/// Get the `i`th argument, which is empty if it was not provided.
+fn arg<'a>(args: &[&'a str], i: usize) -> &'a str {
+    args.get(i).copied().unwrap_or_default()
+}
+
+/// Apply a function to each word of the (only) argument, joining the results with spaces. Words for
+/// which the function returns `None` are dropped.
+fn words(
+    expander: &mut Expander,
+    args: &[&str],
+    f: impl Fn(&str) -> Option<&str>,
+) -> Result<String, String> {
+    let text = expander.expand(args[0])?;
+    Ok(text
+        .split_whitespace()
+        .filter_map(f)
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// Parse the (1-based) index given as an argument to the named function. Unless `allow_zero`, the
+/// index must be positive.
+fn index(function: &str, ordinal: &str, arg: &str, allow_zero: bool) -> Result<usize, String> {
+    let arg = arg.trim();
+    match arg.parse::<usize>() {
+        Err(_) => Err(format!(
+            "Non-numeric {ordinal} argument to '{function}' function: '{arg}'."
+        )),
+        Ok(0) if !allow_zero => Err(format!(
+            "The {ordinal} argument to '{function}' function must be greater than 0."
+        )),
+        Ok(i) => Ok(i),
+    }
+}
+
+/// Get the index of the `.` which starts a word's suffix, if it has one. The suffix must be in the
+/// last component of the path.
+fn suffix_index(word: &str) -> Option<usize> {
+    let dot = word.rfind('.')?;
+    match word.rfind(paths::is_separator) {
+        Some(slash) if slash > dot => None,
+        _ => Some(dot),
+    }
+}
+
+/// `$(abspath names...)`: Make each word of the names an absolute path, relative to the current
+/// directory, collapsing any `.` and `..` components and repeated slashes. This is purely lexical,
+/// so symlinks are not resolved, and the files need not exist.
+fn abspath(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let names = expander.expand(args[0])?;
+    if names.trim().is_empty() {
+        return Ok(String::new());
+    }
+
+    let cwd = env::current_dir().map_err(|e| format!("Failed to get cwd ({e})."))?;
+    let cwd = cwd.to_string_lossy();
+
+    Ok(names
+        .split_whitespace()
+        .map(|name| normalize(&cwd, name))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// Lexically normalize `path` into an absolute path, treating relative paths as relative to `cwd`
+/// (which must be absolute). On Windows, a leading drive (e.g., `C:`) is kept, and the result uses
+/// `/` as its separator.
+fn normalize(cwd: &str, path: &str) -> String {
+    let full = if paths::is_absolute(path) {
+        path.to_string()
+    } else {
+        format!("{cwd}/{path}")
+    };
+
+    let mut parts = paths::components(&full).peekable();
+    let drive = parts.next_if(|p| cfg!(windows) && p.ends_with(':'));
+    let mut components: Vec<&str> = vec![];
+    for component in parts {
+        match component {
+            "" | "." => (),
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+
+    format!("{}/{}", drive.unwrap_or(""), components.join("/"))
+}
+
+/// Combine the (first) argument with each word of the (second) argument, using `f`.
+fn add(
+    expander: &mut Expander,
+    args: &[&str],
+    f: impl Fn(&str, &str) -> String,
+) -> Result<String, String> {
+    let affix = expander.expand(arg(args, 0))?;
+    let names = expander.expand(arg(args, 1))?;
+
+    Ok(names
+        .split_whitespace()
+        .map(|word| f(&affix, word))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// `$(addprefix prefix,names...)`: Prepend the prefix to each word of the names.
+fn addprefix(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    add(expander, args, |prefix, word| format!("{prefix}{word}"))
+}
+
+/// `$(addsuffix suffix,names...)`: Append the suffix to each word of the names.
+fn addsuffix(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    add(expander, args, |suffix, word| format!("{word}{suffix}"))
+}
+
+/// `$(and condition...)`: Expand each (whitespace-stripped) argument in turn, stopping at the
+/// first one which is empty. If all are non-empty, the result is the last one.
+fn and(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let mut result = String::new();
+    for arg in args {
+        result = expander.expand(arg.trim())?;
+        if result.is_empty() {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+/// `$(basename names...)`: Remove the suffix (if any) from each word of the names.
+fn basename(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    words(expander, args, |word| {
+        Some(&word[..suffix_index(word).unwrap_or(word.len())])
+    })
+}
+
+/// `$(call variable,param...)`: Expand the value of the variable, with `$(0)` bound to its name and
+/// `$(1)`, `$(2)`, etc. bound to the (expanded) parameters. The parameters of any enclosing call
+/// are hidden while expanding, so missing parameters are empty.
+fn call_(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let name = expander.expand(arg(args, 0))?.trim().to_string();
+    let params = args[1..]
+        .iter()
+        .map(|param| expander.expand(param))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if expander.calls >= MAX_CALL_DEPTH {
+        return Err(format!(
+            "Calls nested too deeply (over {MAX_CALL_DEPTH}) while calling '{name}'."
+        ));
+    }
+    let body = expander.get(&name).value.clone();
+
+    let saved = take_params(expander);
+    expander.set_local("0", name);
+    for (i, param) in params.into_iter().enumerate() {
+        expander.set_local((i + 1).to_string(), param);
+    }
+
+    expander.calls += 1;
+    let result = expander.expand(&body);
+    expander.calls -= 1;
+
+    take_params(expander);
+    expander.locals.extend(saved);
+    result
+}
+
+/// Remove (and return) the numbered parameters of a `$(call ...)` from the locals.
+fn take_params(expander: &mut Expander) -> Vec<(String, Var)> {
+    let names: Vec<String> = expander
+        .locals
+        .keys()
+        .filter(|k| k.chars().all(|c| c.is_ascii_digit()))
+        .cloned()
+        .collect();
+
+    names
+        .into_iter()
+        .filter_map(|name| expander.locals.remove_entry(&name))
+        .collect()
+}
+
+/// `$(dir names...)`: Get the directory part of each word of the names, which is everything up to
+/// (and including) the last `/`, or `./` if there is none.
+fn dir(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    words(expander, args, |word| {
+        Some(
+            word.rfind(paths::is_separator)
+                .map_or("./", |i| &word[..=i]),
+        )
+    })
+}
+
+/// `$(error text)`: Fail with the expanded text as the message, which stops parsing (or the
+/// recipe being expanded).
+fn error(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    Err(expander.expand(arg(args, 0))?)
+}
+
+/// `$(eval text)`: Parse the expanded text as makefile syntax, expanding to nothing. The text is
+/// only parsed once the enclosing expansion is complete, so it can't affect the rest of that
+/// expansion.
+fn eval(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let text = expander.expand(arg(args, 0))?;
+    expander.evals.push(text);
+    Ok(String::new())
+}
+
+/// `$(expr expression)`: Evaluate an integer arithmetic expression, made of decimal integers, the
+/// operators `+`, `-`, `*`, `/` and `%` (with the usual precedence), and parentheses. Division
+/// truncates toward zero, like the shell's `expr`.
+fn expr(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let expression = expander.expand(arg(args, 0))?;
+    let expression = expression.trim();
+
+    tokenize(expression)
+        .and_then(|tokens| {
+            let mut arithmetic = Arithmetic {
+                tokens: tokens.into_iter().peekable(),
+            };
+            let value = arithmetic.sum()?;
+            match arithmetic.tokens.next() {
+                None => Ok(value.to_string()),
+                Some(token) => Err(format!("Unexpected '{token}'")),
+            }
+        })
+        .map_err(|e| format!("{e} in 'expr' function: '{expression}'."))
+}
+
+/// Split an arithmetic expression into numbers, operators, and parentheses.
+fn tokenize(expression: &str) -> Result<Vec<&str>, String> {
+    let is_operator = |ch: char| "+-*/%()".contains(ch);
+
+    let mut tokens = vec![];
+    let mut rest = expression.trim_start();
+    while let Some(ch) = rest.chars().next() {
+        let len = match is_operator(ch) {
+            true => 1,
+            false => rest
+                .find(|ch: char| ch.is_whitespace() || is_operator(ch))
+                .unwrap_or(rest.len()),
+        };
+        let (token, after) = rest.split_at(len);
+        if !is_operator(ch) && !token.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("Non-numeric operand '{token}'"));
+        }
+        tokens.push(token);
+        rest = after.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent evaluator for the tokens of an arithmetic expression (see `expr`).
+struct Arithmetic<'a> {
+    tokens: Peekable<vec::IntoIter<&'a str>>,
+}
+
+impl Arithmetic<'_> {
+    /// Evaluate terms separated by `+` or `-`.
+    fn sum(&mut self) -> Result<i64, String> {
+        let mut value = self.product()?;
+        while let Some(op) = self.tokens.next_if(|t| matches!(*t, "+" | "-")) {
+            let rhs = self.product()?;
+            value = match op {
+                "+" => value.checked_add(rhs),
+                _ => value.checked_sub(rhs),
+            }
+            .ok_or("Overflow")?;
+        }
+        Ok(value)
+    }
+
+    /// Evaluate factors separated by `*`, `/`, or `%`.
+    fn product(&mut self) -> Result<i64, String> {
+        let mut value = self.factor()?;
+        while let Some(op) = self.tokens.next_if(|t| matches!(*t, "*" | "/" | "%")) {
+            let rhs = self.factor()?;
+            if op != "*" && rhs == 0 {
+                return Err("Division by zero".to_string());
+            }
+            value = match op {
+                "*" => value.checked_mul(rhs),
+                "/" => value.checked_div(rhs),
+                _ => value.checked_rem(rhs),
+            }
+            .ok_or("Overflow")?;
+        }
+        Ok(value)
+    }
+
+    /// Evaluate a number, a parenthesized expression, or a factor with a sign.
+    fn factor(&mut self) -> Result<i64, String> {
+        match self.tokens.next() {
+            Some("-") => Ok(self.factor()?.checked_neg().ok_or("Overflow")?),
+            Some("+") => self.factor(),
+            Some("(") => {
+                let value = self.sum()?;
+                match self.tokens.next() {
+                    Some(")") => Ok(value),
+                    Some(token) => Err(format!("Unexpected '{token}'")),
+                    None => Err("Missing ')'".to_string()),
+                }
+            }
+            Some(token) if token.starts_with(|ch: char| ch.is_ascii_digit()) => {
+                token.parse().map_err(|_| "Overflow".to_string())
+            }
+            Some(token) => Err(format!("Unexpected '{token}'")),
+            None => Err("Missing operand".to_string()),
+        }
+    }
+}
+
+/// `$(file op name[,text])`: Write the text to the named file, either truncating it (with `>`) or
+/// appending to it (with `>>`), and adding a newline unless the text already ends with one. Without
+/// any text, nothing is written, but the file is still created (or truncated). With `<`, the file
+/// is read instead, expanding to its contents with a trailing newline removed (or to nothing if it
+/// doesn't exist). Writing expands to nothing.
+fn file(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let spec = expander.expand(arg(args, 0))?;
+    let spec = spec.trim();
+    let Some((op, name)) = [">>", ">", "<"]
+        .into_iter()
+        .find_map(|op| Some((op, spec.strip_prefix(op)?)))
+    else {
+        return Err(format!(
+            "Invalid file operation for 'file' function: '{spec}'."
+        ));
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("Missing file name for 'file' function: '{spec}'."));
+    }
+
+    match op {
+        "<" => {
+            if args.len() > 1 {
+                return Err(format!(
+                    "Unexpected text when reading '{name}' with 'file' function."
+                ));
+            }
+            match fs::read_to_string(name) {
+                Ok(contents) => Ok(contents.strip_suffix('\n').unwrap_or(&contents).to_string()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+                Err(e) => Err(format!("Failed to read '{name}' ({e}).")),
+            }
+        }
+        _ => {
+            let mut text = String::new();
+            if let Some(raw) = args.get(1) {
+                text = expander.expand(raw)?;
+                if !text.ends_with('\n') {
+                    text.push('\n');
+                }
+            }
+
+            fs::OpenOptions::new()
+                .create(true)
+                .append(op == ">>")
+                .truncate(op == ">")
+                .write(true)
+                .open(name)
+                .and_then(|mut f| f.write_all(text.as_bytes()))
+                .map_err(|e| format!("Failed to write '{name}' ({e})."))?;
+            Ok(String::new())
+        }
+    }
+}
+
+/// `$(filter pattern...,text)` (when `keep` is set) and `$(filter-out pattern...,text)`: Keep (or
+/// remove) the words of the text which match any of the patterns, preserving their order. Matching
+/// is word-wise, so duplicates are kept (or removed) along with the rest.
+fn filter(expander: &mut Expander, args: &[&str], keep: bool) -> Result<String, String> {
+    let patterns = expander.expand(arg(args, 0))?;
+    let patterns: Vec<&str> = patterns.split_whitespace().collect();
+    let text = expander.expand(arg(args, 1))?;
+
+    Ok(text
+        .split_whitespace()
+        .filter(|word| {
+            patterns
+                .iter()
+                .any(|pattern| match_pattern(pattern, word).is_some())
+                == keep
+        })
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// `$(findstring find,in)`: Expand to `find` if it occurs anywhere in `in`, or otherwise to
+/// nothing.
+fn findstring(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let find = expander.expand(arg(args, 0))?;
+    let in_ = expander.expand(arg(args, 1))?;
+    Ok(if in_.contains(&find) {
+        find
+    } else {
+        String::new()
+    })
+}
+
+/// `$(firstword names...)`: Get the first word of the names.
+fn firstword(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let names = expander.expand(args[0])?;
+    Ok(names
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// `$(flavor variable)`: Describe how the variable is expanded (`recursive` or `simple`), or
+/// `undefined` if it is not defined.
+fn flavor(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let name = expander.expand(arg(args, 0))?;
+    let var = expander.get(&name);
+    Ok(match (var.origin, var.recursive) {
+        (Origin::Undefined, _) => "undefined",
+        (_, true) => "recursive",
+        (_, false) => "simple",
+    }
+    .to_string())
+}
+
+/// `$(foreach var,list,text)`: Expand the text once for each word of the list, with `var` bound
+/// to the word, joining the results with spaces. The binding shadows any existing variable, and is
+/// removed afterward.
+fn foreach(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let var = expander.expand(arg(args, 0))?.trim().to_string();
+    let list = expander.expand(arg(args, 1))?;
+
+    let saved = expander.locals.remove(&var);
+    let results = list
+        .split_whitespace()
+        .map(|word| {
+            expander.set_local(var.as_str(), word);
+            expander.expand(arg(args, 2))
+        })
+        .collect::<Result<Vec<_>, _>>();
+
+    match saved {
+        Some(v) => expander.locals.insert(var, v),
+        None => expander.locals.remove(&var),
+    };
+
+    Ok(results?.join(" "))
+}
+
+/// `$(if condition,then[,else])`: Expand to `then` if the (whitespace-stripped) condition expands
+/// to a non-empty string, and otherwise to `else`. Only the branch which is taken is expanded.
+fn if_(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let condition = expander.expand(arg(args, 0).trim())?;
+    let branch = if condition.is_empty() { 2 } else { 1 };
+    expander.expand(arg(args, branch))
+}
+
+/// `$(info text)`: Print the expanded text to stdout, expanding to nothing.
+fn info(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    println!("{}", expander.expand(arg(args, 0))?);
+    Ok(String::new())
+}
+
+/// `$(join list1,list2)`: Concatenate the words of the lists pairwise. If one list is longer, its
+/// extra words are kept unchanged.
+fn join(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let list1 = expander.expand(arg(args, 0))?;
+    let list2 = expander.expand(arg(args, 1))?;
+    let mut words1 = list1.split_whitespace();
+    let mut words2 = list2.split_whitespace();
+
+    let mut joined = vec![];
+    loop {
+        match (words1.next(), words2.next()) {
+            (None, None) => break,
+            (a, b) => joined.push(format!(
+                "{}{}",
+                a.unwrap_or_default(),
+                b.unwrap_or_default()
+            )),
+        }
+    }
+    Ok(joined.join(" "))
+}
+
+/// `$(lastword names...)`: Get the last word of the names.
+fn lastword(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let names = expander.expand(args[0])?;
+    Ok(names
+        .split_whitespace()
+        .last()
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// `$(notdir names...)`: Get the file part of each word of the names, which is everything after
+/// the last `/`. This is empty for words which end with a `/`.
+fn notdir(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    words(expander, args, |word| {
+        Some(
+            word.rfind(paths::is_separator)
+                .map_or(word, |i| &word[i + 1..]),
+        )
+    })
+}
+
+/// `$(or condition...)`: Expand each (whitespace-stripped) argument in turn, stopping at (and
+/// resulting in) the first one which is non-empty.
+fn or(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    for arg in args {
+        let result = expander.expand(arg.trim())?;
+        if !result.is_empty() {
+            return Ok(result);
+        }
+    }
+
+    Ok(String::new())
+}
+
+/// `$(origin variable)`: Describe where the variable's value came from (e.g., `file` or `command
+/// line`), or `undefined` if it is not defined.
+fn origin(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let name = expander.expand(arg(args, 0))?;
+    Ok(expander.get(&name).origin.as_str().to_string())
+}
+
+/// `$(patsubst pattern,replacement,text)`: Replace each whitespace-separated word of the text which
+/// matches the pattern with the replacement, where the `%` in the replacement is the stem.
+fn patsubst(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let pattern = expander.expand(arg(args, 0))?;
+    let replacement = expander.expand(arg(args, 1))?;
+    let text = expander.expand(arg(args, 2))?;
+
+    Ok(text
+        .split_whitespace()
+        .map(|word| match match_pattern(&pattern, word) {
+            Some(stem) => substitute(&replacement, stem),
+            None => word.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// `$(realpath names...)`: Get the canonical absolute path of each word of the names, resolving
+/// symlinks. Names which do not exist (or cannot be resolved) are dropped.
+fn realpath(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let names = expander.expand(args[0])?;
+
+    Ok(names
+        .split_whitespace()
+        .filter_map(|name| fs::canonicalize(name).ok())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// `$(shell command)`: Run the command using `SHELL` and `.SHELLFLAGS`, expanding to its output
+/// with trailing newlines removed and any other newlines converted to spaces. The exit code is
+/// stored in `.SHELLSTATUS`, rather than a failure being treated as an error.
+fn shell(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let script = expander.expand(args[0])?;
+    let shell = expander.expand("$(SHELL)")?;
+    let shell_flags = shell::flags(expander, &shell)?;
+    let makeflags = expander.expand("$(MAKEFLAGS)")?;
+    let makelevel = shell::child_makelevel(&expander.expand("$(MAKELEVEL)")?);
+
+    let output = shell::command(&shell, &shell_flags, &script)
+        .env("MAKEFLAGS", makeflags)
+        .env("MAKELEVEL", makelevel)
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("Failed to run shell `{shell}` ({e})."))?;
+    expander.set_local(".SHELLSTATUS", shell::exit_code(&output.status).to_string());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .trim_end_matches(['\r', '\n'])
+        .replace("\r\n", " ")
+        .replace('\n', " "))
+}
+
+/// `$(sort list)`: Sort the words of the list lexically, removing duplicates.
+fn sort(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let list = expander.expand(args[0])?;
+    let mut words: Vec<&str> = list.split_whitespace().collect();
+    words.sort();
+    words.dedup();
+
+    Ok(words.join(" "))
+}
+
+/// `$(strip string)`: Remove leading and trailing whitespace, and collapse internal whitespace into
+/// single spaces.
+fn strip(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    words(expander, args, |word| Some(word))
+}
+
+/// `$(subst from,to,text)`: Replace every occurrence of `from` in the text with `to`.
+fn subst(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let from = expander.expand(arg(args, 0))?;
+    let to = expander.expand(arg(args, 1))?;
+    let text = expander.expand(arg(args, 2))?;
+
+    // Like GNU make, an empty `from` matches (only) at the end of the text.
+    if from.is_empty() {
+        return Ok(text + &to);
+    }
+
+    Ok(text.replace(&from, &to))
+}
+
+/// `$(suffix names...)`: Get the suffix (starting with the last `.`) of each word of the names.
+/// Words without a suffix are dropped.
+fn suffix(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    words(expander, args, |word| {
+        suffix_index(word).map(|i| &word[i..])
+    })
+}
+
+/// `$(value variable)`: Get the value of the variable without expanding it, i.e., the text of its
+/// definition for a recursive variable, or the already expanded value of a simple one.
+fn value(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let name = expander.expand(arg(args, 0))?;
+    Ok(expander.get(&name).value.clone())
+}
+
+/// `$(warning text)`: Log the expanded text as a warning, expanding to nothing.
+fn warning(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let text = expander.expand(arg(args, 0))?;
+    log_warn(text, expander.context);
+    Ok(String::new())
+}
+
+/// `$(wildcard pattern...)`: Expand to the space-separated, sorted list of existing files matching
+/// any of the patterns. Patterns which match nothing expand to nothing.
+fn wildcard(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let patterns = expander.expand(args[0])?;
+    let mut files = vec![];
+
+    for pattern in patterns.split_whitespace() {
+        if is_glob(pattern) {
+            files.extend(glob(pattern));
+        } else if Path::new(pattern).exists() {
+            files.push(pattern.to_string());
+        }
+    }
+
+    Ok(files.join(" "))
+}
+
+/// `$(word n,text)`: Get the `n`th word (counting from 1) of the text, or nothing if there are
+/// fewer words.
+fn word(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let n = index("word", "first", &expander.expand(arg(args, 0))?, false)?;
+    let text = expander.expand(arg(args, 1))?;
+    Ok(text
+        .split_whitespace()
+        .nth(n - 1)
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// `$(wordlist s,e,text)`: Get the words of the text from the `s`th to the `e`th (inclusive,
+/// counting from 1). Either index may be beyond the last word, which gives fewer (or no) words.
+fn wordlist(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let start = index("wordlist", "first", &expander.expand(arg(args, 0))?, false)?;
+    let end = index("wordlist", "second", &expander.expand(arg(args, 1))?, true)?;
+    let text = expander.expand(arg(args, 2))?;
+    Ok(text
+        .split_whitespace()
+        .skip(start - 1)
+        .take((end + 1).saturating_sub(start))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// `$(words text)`: Count the words of the text.
+fn words_(expander: &mut Expander, args: &[&str]) -> Result<String, String> {
+    let text = expander.expand(args[0])?;
+    Ok(text.split_whitespace().count().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vars::Vars;
+
+    use super::*;
+
+    fn expand(s: &str) -> String {
+        let vars = Vars::new([
+            ("SRCS", "main.c util.c  README"),
+            ("EMPTY", ""),
+            ("x", "global"),
+        ]);
+        Expander::new(&vars).expand(s).unwrap()
+    }
+
+    #[test]
+    fn test_origin() {
+        assert_eq!(expand("$(origin SRCS)"), "file");
+        assert_eq!(expand("$(origin CC)"), "default");
+        assert_eq!(expand("$(origin UNDEFINED)"), "undefined");
+        assert_eq!(expand("$(origin $(x))"), "undefined");
+        assert_eq!(expand("$(foreach v,a,$(origin v))"), "automatic");
+    }
+
+    #[test]
+    fn test_flavor() {
+        let mut vars = Vars::new([("SIMPLE", "b")]);
+        vars.set("RECURSIVE", "$(SIMPLE)", true).unwrap();
+        vars.set("EMPTY", "", true).unwrap();
+        let mut expander = Expander::new(&vars);
+        let mut expand = |s| expander.expand(s).unwrap();
+        assert_eq!(expand("$(flavor SIMPLE)"), "simple");
+        assert_eq!(expand("$(flavor RECURSIVE)"), "recursive");
+        assert_eq!(expand("$(flavor EMPTY)"), "recursive");
+        assert_eq!(expand("$(flavor UNDEFINED)"), "undefined");
+        assert_eq!(expand("$(flavor $(UNDEFINED))"), "undefined");
+    }
+
+    #[test]
+    fn test_value() {
+        let mut vars = Vars::new([("SIMPLE", "b")]);
+        vars.set("RECURSIVE", "$(B) and $$", true).unwrap();
+        vars.set("B", "b", true).unwrap();
+        let mut expander = Expander::new(&vars);
+        let mut expand = |s| expander.expand(s).unwrap();
+        assert_eq!(expand("$(value RECURSIVE)"), "$(B) and $$");
+        assert_eq!(expand("$(RECURSIVE)"), "b and $");
+        assert_eq!(expand("$(value SIMPLE)"), "b");
+        assert_eq!(expand("$(value UNDEFINED)"), "");
+        assert_eq!(
+            expand("$(foreach v,RECURSIVE,$(value $(v)))"),
+            "$(B) and $$"
+        );
+    }
+
+    #[test]
+    fn test_sort() {
+        assert_eq!(expand("$(sort foo bar lose foo)"), "bar foo lose");
+        assert_eq!(expand("$(sort  -Ib -Ia  -Ib )"), "-Ia -Ib");
+        assert_eq!(expand("$(sort $(SRCS))"), "README main.c util.c");
+        assert_eq!(expand("$(sort $(EMPTY))"), "");
+    }
+
+    #[test]
+    fn test_subst() {
+        assert_eq!(
+            expand("$(subst ee,EE,feet on the street)"),
+            "fEEt on the strEEt"
+        );
+        assert_eq!(expand("$(subst .c,.o,$(SRCS))"), "main.o util.o  README");
+        assert_eq!(expand("$(subst a,b,)"), "");
+        assert_eq!(expand("$(subst ,x,abc)"), "abcx");
+    }
+
+    #[test]
+    fn test_abspath() {
+        assert_eq!(expand("$(abspath /a/b/../c/./d //e/)"), "/a/c/d /e");
+        assert_eq!(expand("$(abspath /.. /)"), "/ /");
+        assert_eq!(expand("$(abspath $(EMPTY))"), "");
+
+        let cwd = env::current_dir().unwrap();
+        assert_eq!(expand("$(abspath x/../y)"), cwd.join("y").to_string_lossy());
+    }
+
+    #[test]
+    fn test_realpath() {
+        assert_eq!(expand("$(realpath / /nonexistent/file)"), "/");
+        assert_eq!(
+            expand("$(realpath src/../Cargo.toml)"),
+            fs::canonicalize("Cargo.toml").unwrap().to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_file_names() {
+        assert_eq!(expand("$(dir src/foo.c hacks /abs/x)"), "src/ ./ /abs/");
+        assert_eq!(expand("$(notdir src/foo.c hacks)"), "foo.c hacks");
+        assert_eq!(expand("$(notdir dir/ x)"), " x");
+        assert_eq!(
+            expand("$(suffix src/foo.c src-1.0/bar.c hacks a.b/c)"),
+            ".c .c"
+        );
+        assert_eq!(
+            expand("$(basename src/foo.c src-1.0/bar hacks a.b/c .rc)"),
+            "src/foo src-1.0/bar hacks a.b/c "
+        );
+        assert_eq!(expand("$(addprefix src/,foo bar)"), "src/foo src/bar");
+        assert_eq!(expand("$(addsuffix .c,foo  bar)"), "foo.c bar.c");
+        assert_eq!(expand("$(addsuffix .c,$(EMPTY))"), "");
+    }
+
+    #[test]
+    fn test_filter() {
+        assert_eq!(expand("$(filter %.c %.h,a.c b.h c.o a.c)"), "a.c b.h a.c");
+        assert_eq!(expand("$(filter-out %.c %.h,a.c b.h c.o a.c)"), "c.o");
+        assert_eq!(expand("$(filter README,$(SRCS))"), "README");
+        assert_eq!(expand("$(filter-out README,$(SRCS))"), "main.c util.c");
+        assert_eq!(expand("$(filter,$(SRCS))"), "");
+        assert_eq!(expand("$(filter-out %,$(SRCS))"), "");
+    }
+
+    #[test]
+    fn test_foreach() {
+        assert_eq!(expand("$(foreach f,a b c,$(f).o)"), "a.o b.o c.o");
+        assert_eq!(expand("$(foreach f,$(EMPTY),$(f).o)"), "");
+        assert_eq!(
+            expand("$(foreach a,1 2,$(foreach b,x y,$(a)$(b)))"),
+            "1x 1y 2x 2y"
+        );
+
+        // The binding shadows the global `x`, which is visible again afterward.
+        assert_eq!(expand("$(foreach x,a b,$(x)) $(x)"), "a b global");
+    }
+
+    #[test]
+    fn test_call() {
+        let vars = Vars::new([
+            ("reverse", "$(2) $(1)"),
+            ("name", "$(0)"),
+            ("outer", "$(1)[$(call reverse,x)]$(2)"),
+            ("down", "$(1)$(if $(2),$(call down,$(2),$(3)))"),
+            ("forever", "$(call forever)"),
+        ]);
+        let expand = |s| Expander::new(&vars).expand(s);
+
+        assert_eq!(expand("$(call reverse,a,b)").unwrap(), "b a");
+        assert_eq!(expand("$(call reverse, a ,b c)").unwrap(), "b c  a ");
+        assert_eq!(expand("$(call name)").unwrap(), "name");
+        assert_eq!(expand("$(call undefined,a)").unwrap(), "");
+
+        // The parameters of the enclosing call are hidden, and restored afterward.
+        assert_eq!(expand("$(call outer,a,b)").unwrap(), "a[ x]b");
+
+        // Recursion works, as long as it terminates.
+        assert_eq!(expand("$(call down,a,b,c)").unwrap(), "abc");
+        assert_eq!(
+            expand("$(call forever)").unwrap_err(),
+            "Calls nested too deeply (over 200) while calling 'forever'."
+        );
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        assert_eq!(expand("a$(warning careful)b$(info hello)c"), "abc");
+        assert_eq!(
+            Expander::new(&Vars::new([("x", "global")]))
+                .expand("$(error Failed with $(x))")
+                .unwrap_err(),
+            "Failed with global"
+        );
+    }
+
+    #[test]
+    fn test_expr() {
+        assert_eq!(expand("$(expr 1 + 2 * 3)"), "7");
+        assert_eq!(expand("$(expr (1+2)*3)"), "9");
+        assert_eq!(expand("$(expr $(words $(SRCS)) - 5)"), "-2");
+        assert_eq!(expand("$(expr -7 / 2)"), "-3");
+        assert_eq!(expand("$(expr 7 % -3 + -(2))"), "-1");
+        assert_eq!(expand("$(expr  42 )"), "42");
+
+        let vars = Vars::new([]);
+        let mut expander = Expander::new(&vars);
+        let mut error = |s| expander.expand(s).unwrap_err();
+        assert_eq!(
+            error("$(expr 1 / (2 - 2))"),
+            "Division by zero in 'expr' function: '1 / (2 - 2)'."
+        );
+        assert_eq!(
+            error("$(expr 5 % 0)"),
+            "Division by zero in 'expr' function: '5 % 0'."
+        );
+        assert_eq!(
+            error("$(expr 1 + x)"),
+            "Non-numeric operand 'x' in 'expr' function: '1 + x'."
+        );
+        assert_eq!(
+            error("$(expr 1.5)"),
+            "Non-numeric operand '1.5' in 'expr' function: '1.5'."
+        );
+        assert_eq!(
+            error("$(expr 1 2)"),
+            "Unexpected '2' in 'expr' function: '1 2'."
+        );
+        assert_eq!(error("${expr (1}"), "Missing ')' in 'expr' function: '(1'.");
+        assert_eq!(error("$(expr )"), "Missing operand in 'expr' function: ''.");
+        assert_eq!(
+            error("$(expr 9223372036854775807 + 1)"),
+            "Overflow in 'expr' function: '9223372036854775807 + 1'."
+        );
+    }
+
+    #[test]
+    fn test_file() {
+        let path = env::temp_dir().join(format!("omake-test-file-{}", std::process::id()));
+        let path = path.to_string_lossy();
+        assert_eq!(expand(&format!("$(file >{path},a)")), "");
+        assert_eq!(expand(&format!("$(file >> {path},b\n)")), "");
+        assert_eq!(fs::read_to_string(&*path).unwrap(), "a\nb\n");
+        assert_eq!(expand(&format!("$(file <{path})")), "a\nb");
+        assert_eq!(expand(&format!("$(file >{path})")), "");
+        assert_eq!(fs::read_to_string(&*path).unwrap(), "");
+        fs::remove_file(&*path).unwrap();
+        assert_eq!(expand(&format!("$(file <{path})")), "");
+
+        let vars = Vars::new([]);
+        let mut expander = Expander::new(&vars);
+        assert!(expander.expand("$(file !x,y)").is_err());
+        assert!(expander.expand("$(file >)").is_err());
+        assert!(expander.expand("$(file <x,y)").is_err());
+    }
+
+    #[test]
+    fn test_if() {
+        assert_eq!(expand("$(if $(SRCS),yes,no)"), "yes");
+        assert_eq!(expand("$(if $(EMPTY),yes,no)"), "no");
+        assert_eq!(expand("$(if $(EMPTY),yes)"), "");
+        assert_eq!(expand("$(if  ,yes, no)"), " no");
+        assert_eq!(expand("$(if x, a , b)"), " a ");
+
+        // The branch which is not taken is never expanded.
+        assert_eq!(expand("$(if x,ok,$(error))"), "ok");
+        assert!(Expander::new(&Vars::new([]))
+            .expand("$(if x,$(unclosed,ok)")
+            .is_err());
+    }
+
+    #[test]
+    fn test_or_and() {
+        assert_eq!(expand("$(or $(EMPTY), ,first,second)"), "first");
+        assert_eq!(expand("$(or $(EMPTY))"), "");
+        assert_eq!(expand("$(and a,b, c )"), "c");
+        assert_eq!(expand("$(and a,$(EMPTY),c)"), "");
+    }
+
+    #[test]
+    fn test_patsubst() {
+        assert_eq!(
+            expand("$(patsubst %.c,%.o,$(SRCS))"),
+            "main.o util.o README"
+        );
+        assert_eq!(expand("$(patsubst %.c,obj/%.o,a.c b.h)"), "obj/a.o b.h");
+        assert_eq!(expand("$(patsubst %,x%y,$(EMPTY))"), "");
+        assert_eq!(
+            expand("$(patsubst README,DOC,$(SRCS))"),
+            "main.c util.c DOC"
+        );
+        assert_eq!(expand("$(patsubst %.c,fixed,a.c)"), "fixed");
+    }
+
+    #[test]
+    fn test_findstring() {
+        assert_eq!(expand("$(findstring a,a b c)"), "a");
+        assert_eq!(expand("$(findstring a,b c)"), "");
+        assert_eq!(expand("$(findstring in.c,$(SRCS))"), "in.c");
+        assert_eq!(expand("$(findstring a b,a  b)"), "");
+        assert_eq!(expand("$(findstring ,abc)"), "");
+    }
+
+    #[test]
+    fn test_join() {
+        assert_eq!(expand("$(join a b,.c .o)"), "a.c b.o");
+        assert_eq!(expand("$(join a b c,.c)"), "a.c b c");
+        assert_eq!(expand("$(join a, .c  .o )"), "a.c .o");
+        assert_eq!(expand("$(join $(EMPTY),$(EMPTY))"), "");
+        assert_eq!(
+            expand("$(join $(basename $(SRCS)),$(suffix $(SRCS)))"),
+            "main.c util.c README"
+        );
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(expand("$(strip   a  b	c  )"), "a b c");
+        assert_eq!(expand("$(strip $(EMPTY))"), "");
+        assert_eq!(expand("$(words $(SRCS))"), "3");
+        assert_eq!(expand("$(words $(EMPTY))"), "0");
+        assert_eq!(expand("$(firstword $(SRCS))"), "main.c");
+        assert_eq!(expand("$(lastword $(SRCS))"), "README");
+        assert_eq!(expand("$(lastword $(EMPTY))"), "");
+        assert_eq!(expand("$(word 2,$(SRCS))"), "util.c");
+        assert_eq!(expand("$(word 4,$(SRCS))"), "");
+        assert_eq!(expand("$(wordlist 2,3,a b c d)"), "b c");
+        assert_eq!(expand("$(wordlist 2,9,a b c d)"), "b c d");
+        assert_eq!(expand("$(wordlist 3,2,a b c d)"), "");
+        assert_eq!(expand("$(wordlist 1,0,a b c d)"), "");
+        assert_eq!(expand("$(wordlist 5,6,a b c d)"), "");
+
+        let vars = Vars::new([]);
+        assert_eq!(
+            Expander::new(&vars).expand("$(word 0,a b)").unwrap_err(),
+            "The first argument to 'word' function must be greater than 0."
+        );
+        assert_eq!(
+            Expander::new(&vars).expand("$(word x,a b)").unwrap_err(),
+            "Non-numeric first argument to 'word' function: 'x'."
+        );
+        assert_eq!(
+            Expander::new(&vars)
+                .expand("$(wordlist 0,1,a b)")
+                .unwrap_err(),
+            "The first argument to 'wordlist' function must be greater than 0."
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Printing of the dependency graph (`--print-graph`), which shows which targets depend on which,
+//! as given by the explicit rules of a parsed makefile, in the DOT language of Graphviz. Phony
+//! targets are drawn as boxes (rather than ellipses), and order-only prerequisites with dashed
+//! edges.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use super::pattern::is_pattern;
+use super::Makefile;
+
+impl Makefile {
+    /// Describe the dependency graph of the explicit rules (in definition order) as a DOT digraph.
+    /// Pattern rules and special targets (e.g., `.PHONY`) are left out.
+    pub fn dot_graph(&self) -> String {
+        let mut nodes: Vec<&str> = vec![];
+        let mut edges: Vec<(&str, &str, bool)> = vec![];
+        let mut seen_nodes = HashSet::new();
+        let mut seen_edges = HashSet::new();
+        let mut add_node = |nodes: &mut Vec<_>, node| {
+            if seen_nodes.insert(node) {
+                nodes.push(node);
+            }
+        };
+
+        for rule in self.rule_map.rules() {
+            for target in rule
+                .targets
+                .iter()
+                .filter(|t| !is_special(t) && !is_pattern(t))
+            {
+                add_node(&mut nodes, target.as_str());
+                let prerequisites = rule.prerequisites.iter().map(|p| (p, false));
+                let order_only = rule.order_only.iter().map(|p| (p, true));
+                for (prerequisite, order_only) in prerequisites.chain(order_only) {
+                    // `.WAIT` only orders the prerequisites, and isn't itself one.
+                    if is_special(prerequisite) {
+                        continue;
+                    }
+                    add_node(&mut nodes, prerequisite.as_str());
+                    if seen_edges.insert((target.as_str(), prerequisite.as_str(), order_only)) {
+                        edges.push((target, prerequisite, order_only));
+                    }
+                }
+            }
+        }
+
+        let mut out = String::from("digraph {\n");
+        for node in nodes {
+            let shape = if self.phony.contains(node) {
+                " [shape=box]"
+            } else {
+                ""
+            };
+            let _ = writeln!(out, "  {}{shape};", quote(node));
+        }
+        for (target, prerequisite, order_only) in edges {
+            let style = if order_only { " [style=dashed]" } else { "" };
+            let _ = writeln!(
+                out,
+                "  {} -> {}{style};",
+                quote(target),
+                quote(prerequisite)
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Check whether a target is special (e.g., `.PHONY` or `.DEFAULT`), rather than a file.
+fn is_special(target: &str) -> bool {
+    target.strip_prefix('.').is_some_and(|name| {
+        !name.is_empty() && name.chars().all(|ch| ch.is_ascii_uppercase() || ch == '_')
+    })
+}
+
+/// Quote an identifier for DOT.
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Args, Env};
+
+    #[test]
+    fn test_dot_graph() {
+        let input = concat!(
+            ".PHONY: all\n",
+            "all: app docs\n",
+            "app: main.o util.o | build\n",
+            "\tcc -o $@ $^\n",
+            "%.o: %.c\n",
+            "\tcc -c $<\n",
+            "docs: README .WAIT \"quoted\"\n",
+            "app: main.o\n",
+        );
+        let makefile = Makefile::from_reader(
+            "inline.mk".into(),
+            input.as_bytes(),
+            Args::default(),
+            Env::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            makefile.dot_graph(),
+            concat!(
+                "digraph {\n",
+                "  \"all\" [shape=box];\n",
+                "  \"app\";\n",
+                "  \"docs\";\n",
+                "  \"main.o\";\n",
+                "  \"util.o\";\n",
+                "  \"build\";\n",
+                "  \"README\";\n",
+                "  \"\\\"quoted\\\"\";\n",
+                "  \"all\" -> \"app\";\n",
+                "  \"all\" -> \"docs\";\n",
+                "  \"app\" -> \"main.o\";\n",
+                "  \"app\" -> \"util.o\";\n",
+                "  \"app\" -> \"build\" [style=dashed];\n",
+                "  \"docs\" -> \"README\";\n",
+                "  \"docs\" -> \"\\\"quoted\\\"\";\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_is_special() {
+        assert!(is_special(".PHONY"));
+        assert!(is_special(".DELETE_ON_ERROR"));
+        assert!(!is_special(".depend"));
+        assert!(!is_special("."));
+        assert!(!is_special("all"));
+    }
+}
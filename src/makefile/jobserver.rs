@@ -0,0 +1,308 @@
+//! A POSIX jobserver, which lets recursive invocations of make share a single pool of job slots.
+//!
+//! The pool is a pipe holding one byte (a "token") per job slot, minus one. Each make process also
+//! owns one implicit token, which is why the pool starts one short. Before running a recipe, a job
+//! must acquire a token (the implicit one, if it is free, or else one read from the pipe), and it
+//! writes the token back when done. The pipe's file descriptors are inherited by child processes,
+//! and they are advertised to sub-makes in `MAKEFLAGS` with `--jobserver-auth=R,W`, so that
+//! sub-makes join the existing pool rather than creating their own.
+//!
+//! A job waiting on the pipe must also notice when the implicit token is freed, since the pipe may
+//! stay empty for as long as the jobs of other processes are waiting on us. So, each process also
+//! has a private pipe, which is written to whenever the implicit token is freed, and waiting jobs
+//! poll both.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::sync::Mutex;
+
+use crate::args::Args;
+use crate::error::log_warn;
+
+#[derive(Debug)]
+pub struct Jobserver {
+    read: File,
+    write: File,
+    /// The value for `--jobserver-auth` which allows sub-makes to join this pool.
+    auth: String,
+    /// Whether this process's implicit token is free.
+    implicit: Mutex<bool>,
+    /// The private pipe used to wake up jobs waiting for a token when the implicit token is freed.
+    wake_read: File,
+    wake_write: File,
+}
+
+/// A job slot, which is returned to the pool when dropped.
+pub struct Token<'a> {
+    jobserver: &'a Jobserver,
+    /// The byte read from the pipe, or `None` for the implicit token.
+    byte: Option<u8>,
+}
+
+impl Jobserver {
+    /// Set up the jobserver for the given arguments: serial execution needs no jobserver, a
+    /// sub-make joins the pool named by `--jobserver-auth`, and otherwise we create a pool of
+    /// `jobs` tokens.
+    /// The arguments are updated so `MAKEFLAGS` reflects the outcome.
+    pub fn from_args(args: &mut Args) -> Option<Self> {
+        if args.jobs <= 1 || cfg!(not(unix)) {
+            args.jobserver_auth = None;
+            return None;
+        }
+
+        match &args.jobserver_auth {
+            Some(auth) => match Self::join(auth) {
+                Ok(jobserver) => Some(jobserver),
+                Err(e) => {
+                    log_warn(format!("Jobserver unavailable ({e}); using -j1."), None);
+                    args.jobs = 1;
+                    args.jobserver_auth = None;
+                    None
+                }
+            },
+            None => match Self::new(args.jobs) {
+                Ok(jobserver) => {
+                    args.jobserver_auth = Some(jobserver.auth.clone());
+                    Some(jobserver)
+                }
+                Err(e) => {
+                    log_warn(format!("Failed to create jobserver ({e})."), None);
+                    None
+                }
+            },
+        }
+    }
+
+    /// Create a new pool allowing `jobs` jobs at once.
+    #[cfg(unix)]
+    fn new(jobs: usize) -> io::Result<Self> {
+        use std::os::unix::io::FromRawFd;
+
+        let mut fds = [0; 2];
+        // SAFETY: `fds` is a valid buffer for two descriptors. The descriptors are deliberately not
+        // close-on-exec, so that child processes inherit them.
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: The descriptors were just created, and nothing else owns them.
+        let (read, mut write) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+        write.write_all(&vec![b'+'; jobs - 1])?;
+        let (wake_read, wake_write) = Self::wake_pipe()?;
+
+        Ok(Self {
+            read,
+            write,
+            auth: format!("{},{}", fds[0], fds[1]),
+            implicit: Mutex::new(true),
+            wake_read,
+            wake_write,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn new(_jobs: usize) -> io::Result<Self> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Join an existing pool, given either inherited descriptors (`R,W`) or a named pipe
+    /// (`fifo:PATH`).
+    #[cfg(unix)]
+    fn join(auth: &str) -> io::Result<Self> {
+        use std::fs::OpenOptions;
+        use std::os::unix::io::FromRawFd;
+
+        let (read, write) = if let Some(path) = auth.strip_prefix("fifo:") {
+            let fifo = OpenOptions::new().read(true).write(true).open(path)?;
+            (fifo.try_clone()?, fifo)
+        } else {
+            let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "bad auth string");
+            let (r, w) = auth.split_once(',').ok_or_else(invalid)?;
+            let r: i32 = r.parse().map_err(|_| invalid())?;
+            let w: i32 = w.parse().map_err(|_| invalid())?;
+
+            // If the parent did not pass the descriptors down to us, they are closed (or worse,
+            // reused for something else, which we cannot detect).
+            for fd in [r, w] {
+                // SAFETY: `F_GETFD` only queries the descriptor flags.
+                if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            // SAFETY: The descriptors are open, and were inherited for use as the jobserver.
+            unsafe { (File::from_raw_fd(r), File::from_raw_fd(w)) }
+        };
+
+        let (wake_read, wake_write) = Self::wake_pipe()?;
+
+        Ok(Self {
+            read,
+            write,
+            auth: auth.to_string(),
+            implicit: Mutex::new(true),
+            wake_read,
+            wake_write,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn join(_auth: &str) -> io::Result<Self> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Create the pipe used to wake up jobs waiting for a token. Unlike the pool, it is private to
+    /// this process, so it is close-on-exec, and it is non-blocking, so it can be drained.
+    #[cfg(unix)]
+    fn wake_pipe() -> io::Result<(File, File)> {
+        use std::os::unix::io::{AsRawFd, FromRawFd};
+
+        let mut fds = [0; 2];
+        // SAFETY: `fds` is a valid buffer for two descriptors.
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: The descriptors were just created, and nothing else owns them.
+        let pipe = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+        for fd in [pipe.0.as_raw_fd(), pipe.1.as_raw_fd()] {
+            // SAFETY: These only set the flags of a descriptor we own.
+            if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } == -1
+                || unsafe { libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK) } == -1
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(pipe)
+    }
+
+    /// Acquire a token, blocking until one is available.
+    pub fn acquire(&self) -> io::Result<Token<'_>> {
+        loop {
+            {
+                let mut implicit = self.implicit.lock().unwrap();
+                if *implicit {
+                    *implicit = false;
+                    return Ok(Token {
+                        jobserver: self,
+                        byte: None,
+                    });
+                }
+            }
+
+            if let Some(byte) = self.wait()? {
+                return Ok(Token {
+                    jobserver: self,
+                    byte: Some(byte),
+                });
+            }
+        }
+    }
+
+    /// Wait until either the pool has a token, which is read and returned, or the implicit token
+    /// may have been freed, in which case `None` is returned so the caller can check again.
+    #[cfg(unix)]
+    fn wait(&self) -> io::Result<Option<u8>> {
+        use std::os::unix::io::AsRawFd;
+
+        let mut fds = [self.read.as_raw_fd(), self.wake_read.as_raw_fd()].map(|fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        });
+        // SAFETY: `fds` is a valid array of two descriptors to poll.
+        if unsafe { libc::poll(fds.as_mut_ptr(), 2, -1) } == -1 {
+            let e = io::Error::last_os_error();
+            return match e.kind() {
+                io::ErrorKind::Interrupted => Ok(None),
+                _ => Err(e),
+            };
+        }
+
+        // Drain the wake-up pipe before the caller checks the implicit token again, so a token
+        // freed after that check still wakes us up.
+        if fds[1].revents != 0 {
+            let mut buf = [0; 64];
+            while matches!((&self.wake_read).read(&mut buf), Ok(n) if n > 0) {}
+            return Ok(None);
+        }
+
+        // Another process may take the token first, in which case we block until the next one.
+        let mut byte = [0];
+        loop {
+            match (&self.read).read(&mut byte) {
+                Ok(1) => return Ok(Some(byte[0])),
+                Ok(_) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn wait(&self) -> io::Result<Option<u8>> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+impl Drop for Token<'_> {
+    fn drop(&mut self) {
+        match self.byte {
+            None => {
+                *self.jobserver.implicit.lock().unwrap() = true;
+                let _ = (&self.jobserver.wake_write).write(b"+");
+            }
+            Some(byte) => {
+                let _ = (&self.jobserver.write).write_all(&[byte]);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_returned() {
+        let jobserver = Jobserver::new(2).unwrap();
+        let first = jobserver.acquire().unwrap();
+        let second = jobserver.acquire().unwrap();
+        assert_eq!(first.byte, None);
+        assert_eq!(second.byte, Some(b'+'));
+
+        drop(second);
+        let third = jobserver.acquire().unwrap();
+        assert_eq!(third.byte, Some(b'+'));
+
+        drop(first);
+        assert_eq!(jobserver.acquire().unwrap().byte, None);
+    }
+
+    #[test]
+    fn test_implicit_token_wakes_waiter() {
+        let jobserver = Jobserver::new(1).unwrap();
+        let implicit = jobserver.acquire().unwrap();
+
+        // The pool is empty, so the waiter can only get the implicit token once it is freed.
+        std::thread::scope(|s| {
+            let waiter = s.spawn(|| jobserver.acquire().unwrap().byte);
+            drop(implicit);
+            assert_eq!(waiter.join().unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn test_join() {
+        let jobserver = Jobserver::new(3).unwrap();
+        let joined = Jobserver::join(&jobserver.auth).unwrap();
+        let implicit = joined.acquire().unwrap();
+        assert!(joined.acquire().unwrap().byte.is_some());
+        assert!(Jobserver::join("not,fds").is_err());
+        drop(implicit);
+
+        // Both share the same descriptors, which must only be closed once.
+        std::mem::forget(joined);
+    }
+}
@@ -0,0 +1,177 @@
+//! A manifest of content hashes, for deciding whether prerequisites have changed by their contents
+//! rather than only their `mtime`s (see `--check-hashes`). For each target which has been made, the
+//! manifest records the hash of each prerequisite as it was then. A prerequisite which is newer
+//! than the target, but whose hash matches the recorded one, doesn't cause the target to be remade,
+//! so touching a file (or regenerating it with the same contents) doesn't force a rebuild.
+//!
+//! The target's `mtime` is recorded along with the hashes. If the target has been modified since
+//! (e.g., remade without `--check-hashes`), the hashes no longer describe what it was made from, so
+//! they are dropped.
+//!
+//! The manifest is kept in `MANIFEST_FILE` in the working directory, with one line per target and
+//! prerequisite: the target, its `mtime` (in nanoseconds since the epoch), the prerequisite, and
+//! the hash (in hex), separated by tabs.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// The file (in the working directory) where the manifest is kept.
+pub const MANIFEST_FILE: &str = ".omake-hashes";
+
+/// A function for hashing the contents of a file. Since hashes are compared across runs, it must
+/// be deterministic (unlike `std::collections::hash_map::DefaultHasher`, which may change between
+/// Rust releases).
+pub type HashFn = fn(&[u8]) -> u64;
+
+/// The default `HashFn`: 64-bit FNV-1a, which is fast and good enough to detect changes (but not
+/// tampering).
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// What was recorded about a target when it was last made.
+#[derive(Debug, Default, PartialEq)]
+struct Entry {
+    /// The target's `mtime` (in nanoseconds since the epoch) just after it was made.
+    mtime: u128,
+    /// The hash of each prerequisite (by path).
+    hashes: HashMap<String, u64>,
+}
+
+/// Get the `mtime` of a file in nanoseconds since the epoch, or `None` if it doesn't exist.
+fn file_mtime(path: &str) -> Option<u128> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+#[derive(Debug)]
+pub(super) struct Manifest {
+    hash_fn: HashFn,
+    /// The entry for each target (by path), as of when the target was last made. Entries for
+    /// targets which have been modified since are dropped when they are checked.
+    recorded: RefCell<HashMap<String, Entry>>,
+    /// The current hash of each file (by path), so that each one is only read once. An entry is
+    /// invalidated when its target is remade.
+    hashes: RefCell<HashMap<String, Option<u64>>>,
+    /// Whether `recorded` has changed since it was loaded.
+    dirty: Cell<bool>,
+}
+
+impl Manifest {
+    /// Load the manifest from `path`, which is empty if the file doesn't exist (or is unreadable).
+    /// Malformed lines are ignored.
+    pub fn load(path: impl AsRef<Path>, hash_fn: HashFn) -> Self {
+        let mut recorded: HashMap<String, Entry> = HashMap::new();
+        for line in fs::read_to_string(path).unwrap_or_default().lines() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            if let [target, mtime, prereq, hash] = fields[..] {
+                if let (Ok(mtime), Ok(hash)) = (mtime.parse(), u64::from_str_radix(hash, 16)) {
+                    let entry = recorded.entry(target.to_string()).or_default();
+                    entry.mtime = mtime;
+                    entry.hashes.insert(prereq.to_string(), hash);
+                }
+            }
+        }
+
+        Self {
+            hash_fn,
+            recorded: RefCell::new(recorded),
+            hashes: RefCell::new(HashMap::new()),
+            dirty: Cell::new(false),
+        }
+    }
+
+    /// Hash the current contents of a file, or return `None` if it can't be read.
+    fn hash(&self, path: &str) -> Option<u64> {
+        *self
+            .hashes
+            .borrow_mut()
+            .entry(path.to_string())
+            .or_insert_with(|| fs::read(path).ok().map(|bytes| (self.hash_fn)(&bytes)))
+    }
+
+    /// Check whether the contents of `prereq` are the same as when `target` was last made.
+    pub fn unchanged(&self, target: &str, prereq: &str) -> bool {
+        let mut recorded = self.recorded.borrow_mut();
+        if recorded
+            .get(target)
+            .is_some_and(|entry| Some(entry.mtime) != file_mtime(target))
+        {
+            recorded.remove(target);
+            self.dirty.set(true);
+        }
+
+        let hash = recorded
+            .get(target)
+            .and_then(|entry| entry.hashes.get(prereq));
+        hash.is_some() && hash.copied() == self.hash(prereq)
+    }
+
+    /// Forget the current hash of a file, since it has been remade.
+    pub fn invalidate(&mut self, path: &str) {
+        self.hashes.get_mut().remove(path);
+    }
+
+    /// Record the current hashes of the prerequisites of `target`, which has just been made.
+    /// Prerequisites which can't be read (e.g., phony targets) aren't recorded, and neither is a
+    /// target which wasn't created.
+    pub fn record(&mut self, target: &str, prereqs: &[String]) {
+        let Some(mtime) = file_mtime(target).filter(|_| !target.contains(['\t', '\n'])) else {
+            if self.recorded.get_mut().remove(target).is_some() {
+                self.dirty.set(true);
+            }
+            return;
+        };
+
+        let hashes = prereqs
+            .iter()
+            .filter(|p| !p.contains(['\t', '\n']))
+            .filter_map(|p| Some((p.clone(), self.hash(p)?)))
+            .collect::<HashMap<_, _>>();
+        let entry = Entry { mtime, hashes };
+        if self.recorded.get_mut().get(target) != Some(&entry) {
+            self.recorded.get_mut().insert(target.to_string(), entry);
+            self.dirty.set(true);
+        }
+    }
+
+    /// Write the manifest to `path`, if anything was recorded since it was loaded.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if !self.dirty.get() {
+            return Ok(());
+        }
+
+        let mut lines = vec![];
+        for (target, entry) in self.recorded.borrow().iter() {
+            for (prereq, hash) in &entry.hashes {
+                lines.push(format!(
+                    "{target}\t{}\t{prereq}\t{hash:016x}\n",
+                    entry.mtime
+                ));
+            }
+        }
+        lines.sort();
+        fs::write(path, lines.concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(fnv1a(b"ab"), fnv1a(b"ba"));
+    }
+}
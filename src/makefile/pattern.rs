@@ -0,0 +1,54 @@
+//! Helpers for `%` patterns, as used by pattern rules (and various text functions). A pattern
+//! contains at most one `%`, which matches any non-empty (for rules) sequence of characters called
+//! the "stem". A pattern without a `%` only matches exactly.
+
+const WILDCARD: char = '%';
+
+/// Check if a string is a pattern (i.e., contains a `%`).
+pub fn is_pattern(s: &str) -> bool {
+    s.contains(WILDCARD)
+}
+
+/// Match `s` against `pattern`, returning the stem if it matches. For patterns without a `%`, the
+/// stem of an exact match is empty.
+pub fn match_pattern<'a>(pattern: &str, s: &'a str) -> Option<&'a str> {
+    match pattern.split_once(WILDCARD) {
+        None => (pattern == s).then_some(""),
+        Some((prefix, suffix)) => {
+            if s.len() < prefix.len() + suffix.len() {
+                return None;
+            }
+
+            s.strip_prefix(prefix)?.strip_suffix(suffix)
+        }
+    }
+}
+
+/// Substitute `stem` for the `%` in `pattern`. Patterns without a `%` are returned unchanged.
+pub fn substitute(pattern: &str, stem: &str) -> String {
+    pattern.replacen(WILDCARD, stem, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_pattern() {
+        assert_eq!(match_pattern("%.o", "foo.o"), Some("foo"));
+        assert_eq!(match_pattern("%.o", "dir/foo.o"), Some("dir/foo"));
+        assert_eq!(match_pattern("lib%.a", "libm.a"), Some("m"));
+        assert_eq!(match_pattern("%", "anything"), Some("anything"));
+        assert_eq!(match_pattern("%.o", "foo.c"), None);
+        assert_eq!(match_pattern("a%a", "a"), None);
+        assert_eq!(match_pattern("foo.o", "foo.o"), Some(""));
+        assert_eq!(match_pattern("foo.o", "bar.o"), None);
+    }
+
+    #[test]
+    fn test_substitute() {
+        assert_eq!(substitute("%.c", "foo"), "foo.c");
+        assert_eq!(substitute("src/%.c", "foo"), "src/foo.c");
+        assert_eq!(substitute("none", "foo"), "none");
+    }
+}
@@ -1,32 +1,89 @@
+mod output;
+mod scheduler;
+
 use std::collections::HashMap;
-use std::fs;
-use std::process::Command;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::Path;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::args::Args;
+use crate::args::{Args, MtimePrecision};
 use crate::context::Context;
 use crate::error::{log_info, log_warn, MakeError};
 use crate::makefile::Makefile;
+use crate::paths;
+use crate::signal;
+
+use super::assignment::Assignment;
+use super::expand::Expander;
+use super::is_continued;
+use super::pattern::{is_pattern, match_pattern, substitute};
+use super::shell;
+use output::Output;
+use scheduler::Scheduler;
+
+/// The special prerequisite which makes the prerequisites after it wait for those before it.
+const WAIT: &str = ".WAIT";
 
 /// Get the `mtime` of a file. Note that the return value also signals whether or not the file is
 /// accessible, so a `None` value represents either the file not existing or the current user not
 /// having the appropriate permissions to access the file.
 ///
+/// Symlinks are followed, so a broken symlink is considered missing. When checking symlink times
+/// (`-L`), the symlink's own `mtime` is used instead if it is newer than its target's, or if its
+/// target is missing.
+///
 /// TODO: Consider bailing on a file permissions issue? Not sure if POSIX specifies some behavior
 /// here or if the major implementations halt execution on a permissions error.
 fn get_mtime(file: &String, args: &Args) -> Option<SystemTime> {
-    match fs::metadata(file) {
-        Ok(metadata) => {
-            if args.old_file.contains(file) {
-                Some(UNIX_EPOCH)
-            } else if args.new_file.contains(file) {
-                // 1 year in the future.
-                Some(SystemTime::now() + Duration::from_secs(365 * 24 * 60 * 60))
-            } else {
-                metadata.modified().ok()
-            }
-        }
-        Err(_) => None,
+    let mtime = fs::metadata(file).and_then(|m| m.modified()).ok();
+    let mtime = if args.check_symlink_times {
+        mtime.max(fs::symlink_metadata(file).and_then(|m| m.modified()).ok())
+    } else {
+        mtime
+    }?;
+
+    // A file which is both old and new is considered old.
+    if args.is_old_file(file) {
+        Some(UNIX_EPOCH)
+    } else if args.is_new_file(file) {
+        // 1 year in the future.
+        Some(SystemTime::now() + Duration::from_secs(365 * 24 * 60 * 60))
+    } else {
+        Some(match args.mtime_precision {
+            MtimePrecision::Seconds => truncate_to_seconds(mtime),
+            MtimePrecision::Nanoseconds => mtime,
+        })
+    }
+}
+
+/// Truncate a time to whole seconds (since the Unix epoch).
+fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => UNIX_EPOCH + Duration::from_secs(duration.as_secs()),
+        Err(_) => time,
+    }
+}
+
+/// Delete a target whose recipe failed (for `.DELETE_ON_ERROR`) or was interrupted, since it may be
+/// incomplete but would otherwise look up to date. Only files which the recipe created or modified
+/// are deleted, given the target's `mtime` from before the recipe ran.
+fn delete_partial_target(makefile: &Makefile, target: &str, mtime: Option<SystemTime>) {
+    if makefile.phony.contains(target) {
+        return;
+    }
+
+    let modified = match fs::symlink_metadata(target) {
+        Ok(metadata) if metadata.is_file() => metadata.modified().ok(),
+        _ => return,
+    };
+    if mtime.is_some() && modified == mtime {
+        return;
+    }
+
+    log_info(format!("Deleting file '{target}'."), None);
+    if let Err(e) = fs::remove_file(target) {
+        log_warn(format!("Failed to delete '{target}' ({e})."), None);
     }
 }
 
@@ -35,56 +92,233 @@ fn get_mtime(file: &String, args: &Args) -> Option<SystemTime> {
 pub struct Rule {
     pub targets: Vec<String>,
     pub prerequisites: Vec<String>,
+    /// Prerequisites (after a `|`) which must be made first, but which never cause the target to
+    /// be considered out of date.
+    pub order_only: Vec<String>,
     pub recipe: Vec<String>,
     pub context: Context,
     pub double_colon: bool,
+    /// Whether the rule was given an explicitly empty recipe (e.g., `target: ;`), meaning that the
+    /// target has no recipe, rather than that an implicit rule should be searched for.
+    pub empty_recipe: bool,
+    /// Whether the prerequisites are expanded again when the rule is used, with `$@` and `$*`
+    /// bound (see `.SECONDEXPANSION`).
+    pub second_expansion: bool,
+    /// For a static pattern rule (e.g., `$(OBJS): %.o: %.c`), the pattern which its targets match,
+    /// giving the stem (`$*`). The stem has already been substituted into the prerequisites.
+    pub static_pattern: Option<String>,
 }
 
 impl Rule {
-    pub(super) fn execute(&self, makefile: &Makefile) -> Result<(), MakeError> {
-        let shell = &makefile.vars.get("SHELL").value;
-        let shell_flags = makefile
-            .vars
-            .get(".SHELLFLAGS")
-            .value
-            .split_whitespace()
-            .collect::<Vec<_>>();
-
-        for line in self.recipe.iter() {
-            // Determine if the first character is a command modifier.
-            let command_modifier = match line.chars().next().unwrap() {
-                ch @ ('@' | '-' | '+') => Some(ch),
-                _ => None,
-            };
+    /// Create a normal (single-colon) rule, as if it were defined in a makefile.
+    pub fn new(targets: Vec<String>, prerequisites: Vec<String>, recipe: Vec<String>) -> Self {
+        Self {
+            targets,
+            prerequisites,
+            order_only: vec![],
+            recipe,
+            context: Context::new(),
+            double_colon: false,
+            empty_recipe: false,
+            second_expansion: false,
+            static_pattern: None,
+        }
+    }
+
+    /// Check whether this rule gives a recipe for its targets, even an explicitly empty one.
+    fn has_recipe(&self) -> bool {
+        !self.recipe.is_empty() || self.empty_recipe
+    }
+
+    /// Get the given prerequisites (or order-only prerequisites) of this rule as it applies to
+    /// `target`. With secondary expansion, they are expanded again, with `$@` and `$*` bound.
+    fn expand_prerequisites(
+        &self,
+        makefile: &Makefile,
+        prerequisites: &[String],
+        target: &str,
+        stem: &str,
+    ) -> Result<Vec<String>, MakeError> {
+        if !self.second_expansion {
+            return Ok(prerequisites.to_vec());
+        }
+
+        let automatic_vars = AutomaticVars {
+            target: target.to_string(),
+            stem: stem.to_string(),
+            ..Default::default()
+        };
+        let mut expander = Expander::new(&makefile.vars)
+            .with_context(&self.context)
+            .with_warn_undefined(makefile.args.warn_undefined_variables);
+        automatic_vars.bind(&mut expander);
+
+        let mut expanded = vec![];
+        for prereq in prerequisites {
+            let prereq = expander
+                .expand(prereq)
+                .map_err(|e| MakeError::new(e, self.context.clone()))?;
+            expanded.extend(prereq.split_whitespace().map(|s| s.to_string()));
+        }
+        Ok(expanded)
+    }
+
+    /// Execute this rule's recipe, given the values of the automatic variables (which describe the
+    /// target being made and its prerequisites), sending any output to `output`.
+    pub(super) fn execute(
+        &self,
+        makefile: &Makefile,
+        target_vars: &[&Assignment],
+        automatic_vars: &AutomaticVars,
+        output: &mut Output,
+    ) -> Result<(), MakeError> {
+        // A rule without a recipe only establishes dependencies, so there is nothing to run (or to
+        // take a job slot for).
+        if self.recipe.is_empty() {
+            return Ok(());
+        }
+
+        // Target-specific variables are layered over the global variables, and the automatic
+        // variables over those.
+        let mut expander = Expander::new(&makefile.vars)
+            .with_context(&self.context)
+            .with_warn_undefined(makefile.args.warn_undefined_variables);
+        for assignment in target_vars {
+            expander
+                .assign(assignment)
+                .map_err(|e| MakeError::new(e, self.context.clone()))?;
+        }
+        automatic_vars.bind(&mut expander);
+
+        let expand = |expander: &mut Expander, s| {
+            expander
+                .expand(s)
+                .map_err(|e| MakeError::new(e, self.context.clone()))
+        };
+        let shell = &expand(&mut expander, "$(SHELL)")?;
+        let shell_flags = &shell::flags(&mut expander, shell)
+            .map_err(|e| MakeError::new(e, self.context.clone()))?;
+        let exports = makefile
+            .exported_env(&mut expander, target_vars)
+            .map_err(|e| MakeError::new(e, self.context.clone()))?;
+        let makeflags = &makefile.vars.get("MAKEFLAGS").value;
+        let makelevel = &shell::child_makelevel(&makefile.vars.get("MAKELEVEL").value);
+
+        // When running in parallel, each recipe occupies a job slot in the shared pool.
+        let _token = makefile
+            .jobserver
+            .as_ref()
+            .map(|jobserver| jobserver.acquire())
+            .transpose()
+            .map_err(|e| {
+                MakeError::new(
+                    format!("Failed to acquire a job slot ({e})."),
+                    self.context.clone(),
+                )
+            })?;
 
-            // Echo the line to stdout, unless suppressed.
-            if command_modifier != Some('@') || makefile.args.just_print {
-                println!("{}", line);
+        // Errors may be ignored (and echoing suppressed) for every target, or just for those listed
+        // as prerequisites of `.IGNORE` (and `.SILENT`).
+        let ignore_errors =
+            makefile.args.ignore_errors || makefile.ignore.contains(&automatic_vars.target);
+        let silent = makefile.args.silent || makefile.silent.contains(&automatic_vars.target);
 
-                // If we're just printing, we are done with this line.
-                if makefile.args.just_print {
+        // Expand the whole recipe, and strip any command modifiers, which are not part of the
+        // commands. Lines which invoke a sub-make are implicitly forced, so the sub-make can handle
+        // flags like `-n` itself. A line may expand to several lines (e.g., a canned recipe from a
+        // `define`), each of which is a separate command, with the modifiers of the original line
+        // applying to all of them. Escaped newlines don't separate commands, but are passed to the
+        // shell.
+        let mut commands = vec![];
+        for raw_line in self.recipe.iter() {
+            let (mut outer, raw_line) = Modifiers::parse(raw_line);
+            outer.force |= shell::is_recursive(raw_line);
+
+            for line in split_commands(&expand(&mut expander, raw_line)?) {
+                let (modifiers, command) = Modifiers::parse(&line);
+                if !command.is_empty() {
+                    commands.push((modifiers.or(&outer), command.to_string()));
+                }
+            }
+        }
+
+        // Recipes run once parsing is complete, so there is no makefile left to evaluate into.
+        if !expander.take_evals().is_empty() {
+            return Err(MakeError::new(
+                "Can't use `$(eval ...)` in a recipe.",
+                self.context.clone(),
+            ));
+        }
+
+        // With `.ONESHELL`, the commands are run as a single script, so that shell state (e.g., the
+        // working directory) persists between lines. The modifiers of the first line apply to the
+        // whole script.
+        if makefile.one_shell && !commands.is_empty() {
+            let force = commands.iter().any(|(modifiers, _)| modifiers.force);
+            let script = commands
+                .iter()
+                .map(|(_, command)| command.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let (mut modifiers, _) = commands.swap_remove(0);
+            modifiers.force |= force;
+            commands = vec![(modifiers, script)];
+        }
+
+        // When tracing, each command is also printed along with the target, and where its rule is.
+        let location = match &self.context.path {
+            Some(path) => format!("{}:{}: ", path.display(), self.context.line_number),
+            None => String::new(),
+        };
+
+        let mut validated = false;
+        for (modifiers, command) in commands.iter() {
+            // When touching targets, only forced commands are executed.
+            if makefile.args.touch && !modifiers.force {
+                continue;
+            }
+
+            if makefile.args.trace {
+                output.echo(&format!(
+                    "{location}target '{}' => {command}",
+                    automatic_vars.target
+                ));
+            }
+
+            // Echo the command to stdout, unless suppressed. When just printing, everything is
+            // echoed (with variables already expanded), but only forced commands are executed.
+            if !(silent || modifiers.silent) || makefile.args.just_print {
+                output.echo(command);
+
+                if makefile.args.just_print && !modifiers.force {
                     continue;
                 }
             }
 
-            // Execute the recipe line.
-            let res = Command::new(shell)
-                .args(&shell_flags)
-                .arg(line)
-                .status()
+            // Execute the command, once we know the shell exists.
+            if !validated {
+                shell::validate(shell).map_err(|e| MakeError::new(e, self.context.clone()))?;
+                validated = true;
+            }
+            let mut process = shell::command(shell, shell_flags, command);
+            for (name, value) in &exports {
+                match value {
+                    Some(value) => process.env(name, value),
+                    None => process.env_remove(name),
+                };
+            }
+            let res = output
+                .run(
+                    process
+                        .env("MAKEFLAGS", makeflags)
+                        .env("MAKELEVEL", makelevel),
+                )
                 .map_err(|e| MakeError::new(e.to_string(), self.context.clone()))?;
 
             // Check for command errors, unless directed to ignore them.
-            if command_modifier != Some('-') && !makefile.args.ignore_errors {
-                if let Some(code) = res.code() {
-                    if code != 0 {
-                        return Err(MakeError::new(
-                            format!("Failed with code {}.", code),
-                            self.context.clone(),
-                        ));
-                    }
-                } else {
-                    return Err(MakeError::new("Killed.", self.context.clone()));
+            if !ignore_errors && !modifiers.ignore_errors {
+                if let Some(failure) = shell::describe_failure(command, &res) {
+                    return Err(MakeError::new(failure, self.context.clone()));
                 }
             }
         }
@@ -93,6 +327,156 @@ impl Rule {
     }
 }
 
+/// Split an expanded recipe line into its separate commands, one per line, except that lines ending
+/// in a backslash are continued (see `is_continued`).
+fn split_commands(expanded: &str) -> Vec<String> {
+    let mut commands: Vec<String> = vec![];
+    let mut continued = false;
+    for line in expanded.lines() {
+        match commands.last_mut() {
+            Some(command) if continued => {
+                command.push('\n');
+                command.push_str(line);
+            }
+            _ => commands.push(line.to_string()),
+        }
+        continued = is_continued(line);
+    }
+    commands
+}
+
+/// The modifiers which may prefix a recipe line, in any combination.
+#[derive(Debug, Default)]
+struct Modifiers {
+    /// `@`: Don't echo the command.
+    silent: bool,
+    /// `-`: Ignore errors from the command.
+    ignore_errors: bool,
+    /// `+`: Execute the command, even when just printing.
+    force: bool,
+}
+
+impl Modifiers {
+    /// Split the modifiers from the (expanded) recipe line, returning them along with the command.
+    fn parse(line: &str) -> (Self, &str) {
+        let mut modifiers = Self::default();
+
+        let command = line.trim_start_matches(|ch: char| {
+            match ch {
+                '@' => modifiers.silent = true,
+                '-' => modifiers.ignore_errors = true,
+                '+' => modifiers.force = true,
+                _ => return ch.is_whitespace(),
+            }
+            true
+        });
+
+        (modifiers, command)
+    }
+
+    /// Combine these modifiers with those of an enclosing line.
+    fn or(self, other: &Self) -> Self {
+        Self {
+            silent: self.silent || other.silent,
+            ignore_errors: self.ignore_errors || other.ignore_errors,
+            force: self.force || other.force,
+        }
+    }
+}
+
+/// A rule as it applies to a particular target. For pattern rules, the stem has been substituted
+/// into the prerequisites.
+#[derive(Clone, Debug)]
+struct AppliedRule<'a> {
+    rule: &'a Rule,
+    prerequisites: Vec<String>,
+    order_only: Vec<String>,
+    stem: String,
+}
+
+/// The values of the automatic variables for one execution of a rule's recipe.
+#[derive(Clone, Debug, Default)]
+pub struct AutomaticVars {
+    /// The target being made (`$@`).
+    pub target: String,
+    /// The prerequisites, in order and possibly with duplicates (`$<` and `$^`).
+    pub prerequisites: Vec<String>,
+    /// The prerequisites which are newer than the target (`$?`).
+    pub newer_prerequisites: Vec<String>,
+    /// The order-only prerequisites (`$|`).
+    pub order_only: Vec<String>,
+    /// The stem with which a pattern rule matched the target (`$*`).
+    pub stem: String,
+}
+
+impl AutomaticVars {
+    /// Bind the automatic variables as locals of an `Expander`.
+    fn bind(&self, expander: &mut Expander) {
+        let mut prerequisites: Vec<&str> = vec![];
+        for prereq in &self.prerequisites {
+            if !prerequisites.contains(&prereq.as_str()) {
+                prerequisites.push(prereq);
+            }
+        }
+
+        let newer_prerequisites = self
+            .newer_prerequisites
+            .iter()
+            .map(|s| s.as_str())
+            .collect();
+        let order_only = self.order_only.iter().map(|s| s.as_str()).collect();
+        let first_prerequisite = prerequisites.first().copied().into_iter().collect();
+
+        for (name, words) in [
+            ("@", vec![self.target.as_str()]),
+            ("<", first_prerequisite),
+            ("^", prerequisites),
+            ("?", newer_prerequisites),
+            ("|", order_only),
+            (
+                "*",
+                Some(self.stem.as_str())
+                    .filter(|s| !s.is_empty())
+                    .into_iter()
+                    .collect(),
+            ),
+        ] {
+            // Each variable also has `D` and `F` variants (e.g., `$(@D)`), which are the directory
+            // and file parts of each word.
+            let dirs = words.iter().map(|w| dir_part(w)).collect::<Vec<_>>();
+            let files = words.iter().map(|w| file_part(w)).collect::<Vec<_>>();
+
+            expander.set_local(name, words.join(" "));
+            expander.set_local(format!("{name}D"), dirs.join(" "));
+            expander.set_local(format!("{name}F"), files.join(" "));
+        }
+    }
+}
+
+/// Update the `mtime` of a file to now, creating it if it does not exist.
+fn touch(file: &str) -> io::Result<()> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)?
+        .set_modified(SystemTime::now())
+}
+
+/// Get the directory part of a path, without a trailing slash, or `.` if there is none.
+fn dir_part(path: &str) -> &str {
+    match path.rfind(paths::is_separator) {
+        None => ".",
+        Some(0) => "/",
+        Some(i) => &path[..i],
+    }
+}
+
+/// Get the file part of a path (everything after the last slash).
+fn file_part(path: &str) -> &str {
+    path.rsplit_once(paths::is_separator)
+        .map_or(path, |(_, file)| file)
+}
+
 /// Wrapper for a mapping of targets to rules. We also provide a facility to execute targets.
 #[derive(Debug)]
 pub struct RuleMap {
@@ -102,6 +486,10 @@ pub struct RuleMap {
 
     /// Map targets (strings) to the rules which reference them by index into `self.rules`.
     by_target: HashMap<String, Vec<usize>>,
+
+    /// Pattern rules (i.e., rules whose targets contain a `%`), by index into `self.rules`, in
+    /// definition order. These are not included in `by_target`.
+    pattern_rules: Vec<usize>,
 }
 
 /// Note that methods on `RuleMap` must ensure that only new entries are added to either `rules` or
@@ -112,9 +500,15 @@ impl RuleMap {
         Self {
             rules: vec![],
             by_target: HashMap::new(),
+            pattern_rules: vec![],
         }
     }
 
+    /// Iterate over the rules (including pattern rules), in the order they were inserted.
+    pub fn rules(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter()
+    }
+
     /// Insert a rule, update the `by_target` hashmap, and validate the rule.
     pub fn insert(&mut self, rule: Rule) -> Result<(), MakeError> {
         // Load rule into the storage vector and get a reference to it and the insertion index.
@@ -122,6 +516,20 @@ impl RuleMap {
         self.rules.push(rule);
         let rule = self.rules.last().unwrap();
 
+        // Pattern rules are stored separately, since they are only used when a target has no
+        // explicit rule.
+        if rule.targets.iter().any(|t| is_pattern(t)) {
+            if !rule.targets.iter().all(|t| is_pattern(t)) {
+                return Err(MakeError::new(
+                    "Mixed implicit and normal rules.",
+                    rule.context.clone(),
+                ));
+            }
+
+            self.pattern_rules.push(index);
+            return Ok(());
+        }
+
         // Load each target into `by_target` hashmap and catch some basic validation errors.
         for target in &rule.targets {
             match self.by_target.get_mut(target) {
@@ -139,11 +547,26 @@ impl RuleMap {
                         ));
                     }
 
-                    if rule.double_colon {
-                        rule_indices.push(index);
-                    } else {
-                        log_warn("Ignoring duplicate definition.", Some(&rule.context));
+                    // Like GNU make, a later recipe for the same (single-colon) target overrides
+                    // an earlier one. The rules are merged when the target is made, so the
+                    // prerequisites of all of them still apply.
+                    if !rule.double_colon && rule.has_recipe() {
+                        let old = rule_indices
+                            .iter()
+                            .map(|&i| &self.rules[i])
+                            .rfind(|r| r.has_recipe());
+                        if let Some(old) = old {
+                            log_warn(
+                                format!("Overriding recipe for target '{target}'."),
+                                Some(&rule.context),
+                            );
+                            log_warn(
+                                format!("Ignoring old recipe for target '{target}'."),
+                                Some(&old.context),
+                            );
+                        }
                     }
+                    rule_indices.push(index);
                 }
             }
         }
@@ -151,68 +574,206 @@ impl RuleMap {
         Ok(())
     }
 
-    /// Execute the rules for a particular target, checking prerequisites.
-    pub fn execute(&self, makefile: &Makefile, target: &String) -> Result<(), MakeError> {
-        let rule_indices = self.by_target.get(target).ok_or_else(|| {
-            MakeError::new(
-                format!("No rule to make target '{}'.", target),
-                Context::new(),
-            )
-        })?;
-        let target_mtime_opt = get_mtime(target, &makefile.args);
-
-        // Old files have their rules ignored.
-        if makefile.args.old_file.contains(target) {
-            log_info(
-                format!("Target '{target}' is up to date (old)."),
-                Some(&Context::new()),
-            );
-            return Ok(());
+    /// Find a pattern rule to make `target`, with the stem substituted into the prerequisites.
+    /// When multiple pattern rules match, the one with the shortest stem is chosen (earlier
+    /// definitions winning ties), as long as each of its prerequisites either exists, has an
+    /// explicit rule, or can itself be made by a pattern rule (forming a chain, in which each rule
+    /// is used at most once).
+    ///
+    /// If the pattern has no slash, then only the file part of the target is matched, and the
+    /// directory part is added on the front of the stem and the prerequisites.
+    ///
+    /// With secondary expansion, the prerequisites are expanded before the stem is substituted.
+    fn find_pattern_rule(
+        &self,
+        makefile: &Makefile,
+        target: &str,
+    ) -> Result<Option<AppliedRule<'_>>, MakeError> {
+        self.find_chained_pattern_rule(makefile, target, &[])
+    }
+
+    /// Like `find_pattern_rule`, but without using any of the pattern rules in `chain` (by index
+    /// into `self.rules`), which are already being used to make targets depending on this one.
+    fn find_chained_pattern_rule(
+        &self,
+        makefile: &Makefile,
+        target: &str,
+        chain: &[usize],
+    ) -> Result<Option<AppliedRule<'_>>, MakeError> {
+        let mut candidates = vec![];
+        for &i in self.pattern_rules.iter().filter(|i| !chain.contains(i)) {
+            let rule = &self.rules[i];
+            for pattern in &rule.targets {
+                let (dir, file) = match target.rfind(paths::is_separator) {
+                    Some(slash) if !pattern.contains(paths::is_separator) => {
+                        target.split_at(slash + 1)
+                    }
+                    _ => ("", target),
+                };
+
+                if let Some(stem) = match_pattern(pattern, file).filter(|s| !s.is_empty()) {
+                    candidates.push((i, rule, dir, stem));
+                    break;
+                }
+            }
         }
 
-        let mut executed = false;
-        for i in rule_indices {
-            let rule = &self.rules[i.to_owned()];
-            let mut should_execute = makefile.args.always_make;
-
-            // Check (and possibly execute) prereqs.
-            for prereq in &rule.prerequisites {
-                // Check if prereq exists unless `always_make`.
-                if makefile.args.always_make {
-                    self.execute(makefile, prereq)?;
-                } else {
-                    match get_mtime(prereq, &makefile.args) {
-                        None => {
-                            // Prereq doesn't exist, so make it. By definition, it's more up-to-date
-                            // than the target.
-                            self.execute(makefile, prereq)?;
-                            should_execute = true;
-                        }
-                        Some(prereq_mtime) => {
-                            // Prereq exists, so check if it's more up-to-date than the target.
-                            if let Some(target_mtime) = target_mtime_opt {
-                                if prereq_mtime > target_mtime {
-                                    should_execute = true;
-                                }
+        // This sort is stable, so earlier definitions win ties.
+        candidates.sort_by_key(|(_, _, dir, stem)| dir.len() + stem.len());
+
+        for (i, rule, dir, stem) in candidates {
+            let full_stem = format!("{dir}{stem}");
+            let apply = |prerequisites: &Vec<String>| {
+                let prerequisites =
+                    rule.expand_prerequisites(makefile, prerequisites, target, &full_stem)?;
+                Ok::<_, MakeError>(
+                    prerequisites
+                        .into_iter()
+                        .map(|p| {
+                            if is_pattern(&p) {
+                                format!("{dir}{}", substitute(&p, stem))
+                            } else {
+                                p
                             }
-                        }
-                    }
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            };
+            let applied = AppliedRule {
+                rule,
+                prerequisites: apply(&rule.prerequisites)?,
+                order_only: apply(&rule.order_only)?,
+                stem: full_stem.clone(),
+            };
+
+            let chain = [chain, &[i]].concat();
+            let mut viable = true;
+            for p in applied.prerequisites.iter().chain(&applied.order_only) {
+                viable = p == WAIT
+                    || self.by_target.contains_key(p)
+                    || Path::new(p).exists()
+                    || makefile.vpath_search(p).is_some()
+                    || self
+                        .find_chained_pattern_rule(makefile, p, &chain)?
+                        .is_some();
+                if !viable {
+                    break;
                 }
             }
+            if viable {
+                return Ok(Some(applied));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Execute the rules for the given targets, checking prerequisites. The actual work is done by
+    /// the `Scheduler`, which may run independent targets concurrently (see `-j`).
+    pub fn execute(&self, makefile: &Makefile, targets: &[String]) -> Result<(), MakeError> {
+        Scheduler::new(makefile, self).execute(targets)
+    }
+
+    /// Determine whether the targets are up to date.
+    pub fn question(&self, makefile: &Makefile, targets: &[String]) -> Result<bool, MakeError> {
+        Scheduler::new(makefile, self).question(targets)
+    }
+
+    /// Execute the given rules in order, stopping at the first failure.
+    fn execute_rules(
+        makefile: &Makefile,
+        target_vars: &[&Assignment],
+        rules: &[(&Rule, AutomaticVars)],
+        output: &mut Output,
+    ) -> Result<(), MakeError> {
+        // Note the target's `mtime` beforehand, so we know whether a failed recipe modified it.
+        let target = rules
+            .first()
+            .map(|(_, automatic_vars)| &automatic_vars.target);
+        let mtime = target.and_then(|target| fs::metadata(target).and_then(|m| m.modified()).ok());
 
-            if target_mtime_opt.is_none() || should_execute {
-                rule.execute(makefile)?;
-                executed = true;
+        for (rule, automatic_vars) in rules {
+            if let Err(e) = rule.execute(makefile, target_vars, automatic_vars, output) {
+                let interrupted = signal::pending().is_some();
+                if (makefile.delete_on_error || interrupted) && !makefile.args.just_print {
+                    delete_partial_target(makefile, &automatic_vars.target, mtime);
+                }
+                return Err(e);
             }
         }
 
-        if !executed {
-            log_info(
-                format!("Target '{target}' is up to date."),
-                Some(&Context::new()),
-            );
+        // When touching, the target is marked as up to date, rather than being remade.
+        if makefile.args.touch {
+            if let Some((rule, automatic_vars)) = rules.first() {
+                let target = &automatic_vars.target;
+                if !makefile.phony.contains(target) {
+                    output.echo(&format!("touch {target}"));
+                    if !makefile.args.just_print {
+                        touch(target).map_err(|e| {
+                            MakeError::new(
+                                format!("Failed to touch '{target}' ({e})."),
+                                rule.context.clone(),
+                            )
+                        })?;
+                    }
+                }
+            }
         }
 
         Ok(())
     }
 }
+
+impl Default for RuleMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vars::Env;
+
+    #[test]
+    fn test_truncate_to_seconds() {
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 999_999_999);
+        assert_eq!(
+            truncate_to_seconds(time),
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+        assert_eq!(truncate_to_seconds(UNIX_EPOCH), UNIX_EPOCH);
+    }
+
+    #[test]
+    fn test_rules_are_expanded_when_defined() {
+        let input = concat!(
+            "SRCS := a.c b.c\n",
+            "OBJS = $(patsubst %.c,%.o,$(SRCS)) $(LATER)\n",
+            "PROG = app\n",
+            "$(PROG) $(subst :,-,x:y): $(OBJS) | $(PROG).dir\n",
+            "\techo $(LATER)\n",
+            "LATER = c.o\n",
+        );
+        let makefile = Makefile::from_reader(
+            "Makefile".into(),
+            input.as_bytes(),
+            Args::default(),
+            Env::new(),
+        )
+        .unwrap();
+        let rule_map = &makefile.rule_map;
+
+        for target in ["app", "x-y"] {
+            let rules = &rule_map.by_target[target];
+            assert_eq!(rules.len(), 1);
+
+            let rule = &rule_map.rules[rules[0]];
+            assert_eq!(rule.prerequisites, ["a.o", "b.o"]);
+            assert_eq!(rule.order_only, ["app.dir"]);
+
+            // Recipes are only expanded when they run.
+            assert_eq!(rule.recipe, ["echo $(LATER)"]);
+        }
+    }
+}
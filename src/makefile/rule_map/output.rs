@@ -0,0 +1,187 @@
+//! Handling of the output of recipes. Normally, recipes inherit our `STDOUT` and `STDERR`, but when
+//! running jobs in parallel with `--output-sync`, the output of each target is buffered so it can
+//! be flushed all at once when the target completes. With `--prefix-output`, each line of output is
+//! prefixed with the name of the target which produced it.
+//!
+//! Recipes also inherit our `STDIN`, except for parallel jobs (unless `--stdin=inherit`), which
+//! read from the null device instead, so that concurrent jobs don't compete for the terminal's
+//! input (or hang waiting for it while their output is buffered).
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+
+use crate::args::{Args, OutputSync, StdinMode};
+use crate::signal::{self, Tracked};
+
+#[derive(Debug)]
+pub struct Output {
+    sink: Sink,
+    /// Whether recipes read from the null device, rather than inheriting our `STDIN`.
+    null_stdin: bool,
+}
+
+#[derive(Debug)]
+enum Sink {
+    /// Recipes write directly to our `STDOUT` and `STDERR`.
+    Inherit,
+    /// Recipe output is streamed line by line to our `STDOUT` and `STDERR`, with a prefix.
+    Prefix(String),
+    /// Output is buffered (with each line prefixed, if given) until `flush` is called.
+    Buffer {
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+        prefix: Option<String>,
+    },
+}
+
+impl Output {
+    /// Create the output for making `target`. Buffering only applies to `parallel` jobs, since
+    /// serial output is never interleaved.
+    pub fn new(args: &Args, target: &str, parallel: bool) -> Self {
+        let prefix = args.prefix_output.then(|| format!("[{target}] "));
+        let sink = match (prefix, parallel && args.output_sync == OutputSync::Target) {
+            (prefix, true) => Sink::Buffer {
+                stdout: vec![],
+                stderr: vec![],
+                prefix,
+            },
+            (Some(prefix), false) => Sink::Prefix(prefix),
+            (None, false) => Sink::Inherit,
+        };
+        let null_stdin = match args.stdin {
+            StdinMode::Auto => parallel,
+            StdinMode::Inherit => false,
+            StdinMode::Null => true,
+        };
+
+        Self { sink, null_stdin }
+    }
+
+    /// Echo a recipe line.
+    pub fn echo(&mut self, line: &str) {
+        match &mut self.sink {
+            Sink::Inherit => println!("{}", line),
+            Sink::Prefix(prefix) => println!("{prefix}{line}"),
+            Sink::Buffer { stdout, prefix, .. } => {
+                let line = format!("{line}\n");
+                stdout.extend(prefix_lines(prefix.as_deref(), line.as_bytes()));
+            }
+        }
+    }
+
+    /// Run a command to completion, with its output directed appropriately.
+    pub fn run(&mut self, command: &mut Command) -> io::Result<ExitStatus> {
+        if self.null_stdin {
+            command.stdin(Stdio::null());
+        }
+
+        match &mut self.sink {
+            Sink::Inherit => {
+                let (mut child, _tracked) = spawn(command)?;
+                child.wait()
+            }
+            Sink::Prefix(prefix) => {
+                let (mut child, _tracked) =
+                    spawn(command.stdout(Stdio::piped()).stderr(Stdio::piped()))?;
+                let child_stdout = child.stdout.take().expect("stdout is piped");
+                let child_stderr = child.stderr.take().expect("stderr is piped");
+
+                // Both pipes must be drained concurrently, otherwise a child which fills one of
+                // them would block forever while we wait on the other.
+                let prefix = prefix.as_str();
+                thread::scope(|scope| {
+                    scope.spawn(|| stream_prefixed(child_stdout, prefix, io::stdout));
+                    stream_prefixed(child_stderr, prefix, io::stderr);
+                });
+
+                child.wait()
+            }
+            Sink::Buffer {
+                stdout,
+                stderr,
+                prefix,
+            } => {
+                // `wait_with_output` already reads both pipes concurrently.
+                let (child, _tracked) =
+                    spawn(command.stdout(Stdio::piped()).stderr(Stdio::piped()))?;
+                let output = child.wait_with_output()?;
+                stdout.extend(prefix_lines(prefix.as_deref(), &output.stdout));
+                stderr.extend(prefix_lines(prefix.as_deref(), &output.stderr));
+                Ok(output.status)
+            }
+        }
+    }
+
+    /// Write any buffered output to our `STDOUT` and `STDERR`.
+    pub fn flush(self) {
+        if let Sink::Buffer { stdout, stderr, .. } = self.sink {
+            let _ = io::stdout().lock().write_all(&stdout);
+            let _ = io::stderr().lock().write_all(&stderr);
+        }
+    }
+}
+
+/// Spawn a command. When interrupts are handled, it is the leader of a new process group, which is
+/// tracked so that interrupts are forwarded to everything it runs.
+fn spawn(command: &mut Command) -> io::Result<(Child, Option<Tracked>)> {
+    if !signal::installed() {
+        return Ok((command.spawn()?, None));
+    }
+
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    let child = command.spawn()?;
+    let tracked = Tracked::new(&child);
+    Ok((child, Some(tracked)))
+}
+
+/// Prefix each line of `bytes`. As when streaming, a trailing partial line is terminated.
+fn prefix_lines(prefix: Option<&str>, bytes: &[u8]) -> Vec<u8> {
+    let Some(prefix) = prefix else {
+        return bytes.to_vec();
+    };
+
+    let mut prefixed = Vec::with_capacity(bytes.len());
+    for line in bytes.split_inclusive(|&b| b == b'\n') {
+        prefixed.extend_from_slice(prefix.as_bytes());
+        prefixed.extend_from_slice(line);
+        if !line.ends_with(b"\n") {
+            prefixed.push(b'\n');
+        }
+    }
+    prefixed
+}
+
+/// Copy `reader` to the stream returned by `writer` line by line, adding `prefix` to each line. The
+/// stream is locked for each line, so lines from concurrent jobs are never mixed. A trailing
+/// partial line is terminated so the next prefix starts on a new line.
+fn stream_prefixed<W: Write>(reader: impl Read, prefix: &str, writer: impl Fn() -> W) {
+    let mut reader = BufReader::new(reader);
+    let mut line = prefix.as_bytes().to_vec();
+    loop {
+        line.truncate(prefix.len());
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if line.last() != Some(&b'\n') {
+                    line.push(b'\n');
+                }
+                let _ = writer().write_all(&line);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_lines() {
+        assert_eq!(prefix_lines(None, b"a\nb\n"), b"a\nb\n");
+        assert_eq!(prefix_lines(Some("[t] "), b"a\nb\n"), b"[t] a\n[t] b\n");
+        assert_eq!(prefix_lines(Some("[t] "), b"a\nb"), b"[t] a\n[t] b\n");
+        assert_eq!(prefix_lines(Some("[t] "), b""), b"");
+    }
+}
@@ -0,0 +1,953 @@
+//! A dependency-graph driven executor for the rules in a `RuleMap`.
+//!
+//! Execution happens in two phases. First, the requested targets are resolved against the
+//! `RuleMap` into a graph of `Node`s (one per distinct target), where each node knows which rules
+//! apply to it and which nodes are its prerequisites. While resolving, we record the nodes in
+//! depth-first post-order, which is exactly the order in which a serial, recursive make would build
+//! them. Second, we repeatedly dispatch the first pending node (in that order) whose prerequisites
+//! have all completed, keeping up to `jobs` nodes in flight at once. This means `-j1` degenerates
+//! to the traditional depth-first execution order, while higher job counts let independent
+//! branches of the graph run concurrently.
+//!
+//! Ordering constraints between prerequisites (from `.WAIT` and `.NOTPARALLEL`) are additional
+//! edges in the graph, which delay a node until the nodes it waits for have completed.
+//!
+//! With a load limit (`-l`), no more nodes are dispatched while any are running and the system's
+//! load average is at or above the limit, which is rechecked every `LOAD_RECHECK_INTERVAL`.
+//!
+//! With `--stats`, the wall-clock time of each target's recipes is recorded, and the slowest ones
+//! are reported once the build is finished, along with its total time.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::output::Output;
+use super::{
+    get_mtime, is_pattern, match_pattern, AppliedRule, AutomaticVars, Rule, RuleMap, WAIT,
+};
+use crate::args::DebugFlag;
+use crate::context::Context;
+use crate::error::{log_debug, log_error, log_info, log_warn, MakeError};
+use crate::makefile::assignment::Assignment;
+use crate::makefile::manifest::{Manifest, MANIFEST_FILE};
+use crate::makefile::shell;
+use crate::makefile::Makefile;
+use crate::signal;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Pending,
+    Running,
+    Done,
+    /// Making the target failed.
+    Failed,
+    /// The target was not attempted, because a prerequisite failed.
+    Skipped,
+}
+
+/// Represents a single target in the dependency graph.
+#[derive(Debug)]
+struct Node<'a> {
+    target: String,
+    /// Where the target's file is, which differs from `target` when it was found through directory
+    /// search (see `VPATH`).
+    path: String,
+    /// The rules which apply to this target.
+    rules: Vec<AppliedRule<'a>>,
+    /// The prerequisites of all of the rules (by index into `Scheduler::nodes`), without
+    /// duplicates.
+    prerequisites: Vec<usize>,
+    /// Nodes which must complete before this one starts, because they come before it in some
+    /// prerequisite list (with `.WAIT` in between, or under `.NOTPARALLEL`).
+    wait_for: Vec<usize>,
+    /// The target-specific variable assignments which apply to this target, including those
+    /// inherited from the target which first required it, in the order they should be applied.
+    vars: Vec<&'a Assignment>,
+    /// The target (and rule) which first required this one as a prerequisite, if any.
+    required_by: Option<(String, &'a Rule)>,
+    /// Whether this target was requested directly, rather than only as a prerequisite.
+    goal: bool,
+    /// Whether this target's file is deleted once the build is finished, if it was made (see
+    /// `Scheduler::is_intermediate`).
+    intermediate: bool,
+    state: State,
+}
+
+pub(super) struct Scheduler<'a> {
+    makefile: &'a Makefile,
+    rule_map: &'a RuleMap,
+    nodes: Vec<Node<'a>>,
+    by_target: HashMap<String, usize>,
+
+    /// Node indices in depth-first post-order, which is the order nodes are dispatched in.
+    order: Vec<usize>,
+
+    /// Node indices which are currently being resolved.
+    stack: Vec<usize>,
+
+    /// The modification times of files (by path), so that each one is only checked once. An entry
+    /// is invalidated when its target is remade.
+    mtimes: RefCell<HashMap<String, Option<SystemTime>>>,
+
+    /// The hashes of prerequisites as of when their targets were made, with `--check-hashes`.
+    manifest: Option<Manifest>,
+
+    /// How long the recipes of each target took to run, with `--stats`.
+    timings: Vec<(String, Duration)>,
+}
+
+/// How many of the slowest targets `--stats` reports.
+const STATS_TARGETS: usize = 10;
+
+/// How often to recheck the load average while dispatching is held back by `-l`.
+const LOAD_RECHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Get the system's load average over the last minute, where it is available.
+fn load_average() -> Option<f64> {
+    #[cfg(unix)]
+    {
+        let mut load = [0.0];
+        // SAFETY: The buffer has room for the one sample requested.
+        if unsafe { libc::getloadavg(load.as_mut_ptr(), 1) } == 1 {
+            return Some(load[0]);
+        }
+    }
+
+    None
+}
+
+impl<'a> Scheduler<'a> {
+    pub fn new(makefile: &'a Makefile, rule_map: &'a RuleMap) -> Self {
+        Self {
+            makefile,
+            rule_map,
+            nodes: vec![],
+            by_target: HashMap::new(),
+            order: vec![],
+            stack: vec![],
+            mtimes: RefCell::new(HashMap::new()),
+            manifest: makefile
+                .args
+                .check_hashes
+                .then(|| Manifest::load(MANIFEST_FILE, makefile.hash_fn)),
+            timings: vec![],
+        }
+    }
+
+    /// Principal interface for building the given targets.
+    pub fn execute(mut self, targets: &[String]) -> Result<(), MakeError> {
+        let started = Instant::now();
+        for target in targets {
+            let index = self.resolve(target, &[])?;
+            self.nodes[index].goal = true;
+        }
+
+        // `.NOTPARALLEL` without prerequisites makes the whole build serial.
+        let jobs = if self.makefile.not_parallel.is_all() {
+            1
+        } else {
+            self.makefile.args.jobs.max(1)
+        };
+        let keep_going = self.makefile.args.keep_going;
+        let makefile = self.makefile;
+
+        // A load limit only applies where the load average can be read.
+        let max_load = self.makefile.args.max_load.filter(|_| {
+            let available = load_average().is_some();
+            if !available {
+                log_warn(
+                    "Ignoring the load limit, since the load average is not available.",
+                    None,
+                );
+            }
+            available
+        });
+        let overloaded = || max_load.is_some_and(|max| load_average().is_some_and(|l| l >= max));
+
+        thread::scope(|scope| {
+            let (tx, rx) = mpsc::channel();
+            let mut running = 0;
+            let mut error = None;
+
+            loop {
+                // Dispatch as many ready nodes as we are allowed to. An error stops dispatching,
+                // unless we are keeping going, and an interrupt always does.
+                while (keep_going || error.is_none())
+                    && signal::pending().is_none()
+                    && running < jobs
+                    && (running == 0 || !overloaded())
+                {
+                    let Some(index) = self.next_ready() else {
+                        break;
+                    };
+
+                    // Targets depending on a failed prerequisite are not attempted.
+                    if self.nodes[index]
+                        .prerequisites
+                        .iter()
+                        .any(|&p| matches!(self.nodes[p].state, State::Failed | State::Skipped))
+                    {
+                        self.skip(index);
+                        continue;
+                    }
+
+                    let rules = match self.outdated_rules(index) {
+                        Err(e) => {
+                            self.fail(index, e, &mut error);
+                            continue;
+                        }
+                        Ok(rules) => rules,
+                    };
+
+                    if rules.is_empty() {
+                        self.finish(index, false);
+                    } else if jobs == 1 {
+                        // Serial execution happens inline to behave exactly like a recursive make.
+                        let node = &self.nodes[index];
+                        let mut output = Output::new(&makefile.args, &node.target, false);
+                        let start = Instant::now();
+                        let result =
+                            RuleMap::execute_rules(makefile, &node.vars, &rules, &mut output);
+                        self.record_time(index, start.elapsed());
+                        match result {
+                            Err(e) => self.fail(index, e, &mut error),
+                            Ok(()) => self.finish(index, true),
+                        }
+                    } else {
+                        self.nodes[index].state = State::Running;
+                        running += 1;
+                        let tx = tx.clone();
+                        let vars = self.nodes[index].vars.clone();
+                        let mut output =
+                            Output::new(&makefile.args, &self.nodes[index].target, true);
+                        scope.spawn(move || {
+                            let start = Instant::now();
+                            let result =
+                                RuleMap::execute_rules(makefile, &vars, &rules, &mut output);
+                            let _ = tx.send((index, result, output, start.elapsed()));
+                        });
+                    }
+                }
+
+                if running == 0 {
+                    break;
+                }
+
+                // Wait for a running job to complete. Even after an error, we must still wait for
+                // any other jobs that are already in flight. Since only this thread flushes
+                // buffered output, the output of each target is never interleaved. With a load
+                // limit, we stop waiting periodically to check whether another job may start.
+                let (index, result, output, elapsed) = match max_load {
+                    None => rx.recv().expect("running jobs hold a sender"),
+                    Some(_) => match rx.recv_timeout(LOAD_RECHECK_INTERVAL) {
+                        Ok(message) => message,
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        Err(RecvTimeoutError::Disconnected) => {
+                            unreachable!("running jobs hold a sender")
+                        }
+                    },
+                };
+                output.flush();
+                running -= 1;
+                self.record_time(index, elapsed);
+                match result {
+                    Err(e) => self.fail(index, e, &mut error),
+                    Ok(()) => self.finish(index, true),
+                }
+            }
+
+            self.delete_intermediates();
+            if let Some(manifest) = &self.manifest {
+                if let Err(e) = manifest.save(MANIFEST_FILE) {
+                    log_warn(format!("Failed to save '{MANIFEST_FILE}' ({e})."), None);
+                }
+            }
+            if makefile.args.stats {
+                for line in stats_report(std::mem::take(&mut self.timings), started.elapsed()) {
+                    log_info(line, None);
+                }
+            }
+
+            // An interrupt takes precedence over the errors it caused in running recipes.
+            if let Some(signal) = signal::pending() {
+                return Err(MakeError::new(
+                    format!("Interrupted by {}.", shell::describe_signal(signal)),
+                    Context::new(),
+                ));
+            }
+            if let Some(e) = error {
+                return Err(e);
+            }
+
+            // When keeping going, the individual errors have already been logged, so summarize.
+            let failed = self
+                .order
+                .iter()
+                .filter(|&&i| self.nodes[i].state == State::Failed)
+                .map(|&i| self.nodes[i].target.as_str())
+                .collect::<Vec<_>>();
+            if failed.is_empty() {
+                Ok(())
+            } else {
+                Err(MakeError::new(
+                    format!("Failed to make targets: {}.", failed.join(", ")),
+                    Context::new(),
+                ))
+            }
+        })
+    }
+
+    /// Determine whether the given targets are up to date, without executing anything. A target is
+    /// not up to date if any recipe would be run to make it (or any of its prerequisites).
+    pub fn question(mut self, targets: &[String]) -> Result<bool, MakeError> {
+        for target in targets {
+            let index = self.resolve(target, &[])?;
+            self.nodes[index].goal = true;
+        }
+
+        for &index in &self.order {
+            let rules = self.outdated_rules(index)?;
+            if rules.iter().any(|(rule, _)| !rule.recipe.is_empty()) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Resolve a target (and, recursively, its prerequisites) into a node, returning the node's
+    /// index. Each target is only resolved once. Since this is a depth-first traversal, the nodes
+    /// currently being resolved are tracked on `self.stack`, so we can detect circular
+    /// dependencies.
+    fn resolve(&mut self, target: &str, inherited: &[&'a Assignment]) -> Result<usize, MakeError> {
+        if let Some(index) = self.by_target.get(target) {
+            return Ok(*index);
+        }
+
+        // Old files have their rules ignored.
+        let rules = if self.makefile.args.is_old_file(target) {
+            vec![]
+        } else {
+            self.find_rules(target)?
+        };
+
+        // Files which are not in the current directory may be found through directory search.
+        let path = if self.makefile.phony.contains(target) || Path::new(target).exists() {
+            None
+        } else {
+            self.makefile.vpath_search(target)
+        };
+
+        // Target-specific variables are inherited by prerequisites, from whichever target first
+        // requires them. A target's own (pattern-specific, then target-specific) variables are
+        // applied last, so they take precedence over inherited ones, which take precedence over
+        // global ones.
+        let mut vars = inherited.to_vec();
+        vars.extend(self.makefile.target_vars(target));
+
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            target: target.to_string(),
+            path: path.unwrap_or_else(|| target.to_string()),
+            rules: vec![],
+            prerequisites: vec![],
+            wait_for: vec![],
+            vars: vars.clone(),
+            required_by: None,
+            goal: false,
+            intermediate: false,
+            state: State::Pending,
+        });
+        self.by_target.insert(target.to_string(), index);
+        self.stack.push(index);
+        self.nodes[index].intermediate = self.is_intermediate(index, false);
+
+        // Under `.NOTPARALLEL`, every prerequisite waits for the ones before it.
+        let serial = self.makefile.not_parallel.contains(target);
+
+        let mut prerequisites = vec![];
+        for rule in &rules {
+            let via_pattern = rule.rule.targets.iter().any(|t| is_pattern(t));
+
+            // The prerequisites before the latest `.WAIT`, and those since.
+            let mut before = vec![];
+            let mut group = vec![];
+
+            // Order-only prerequisites must also be made first, but they never cause the target to
+            // be considered out of date.
+            for prereq in rule.prerequisites.iter().chain(&rule.order_only) {
+                if prereq == WAIT {
+                    before.append(&mut group);
+                    continue;
+                }
+
+                if let Some(&prereq_index) = self.by_target.get(prereq) {
+                    if self.stack.contains(&prereq_index) {
+                        return Err(self.circular_dependency(prereq_index, rule.rule));
+                    }
+                }
+
+                let prereq_index = self.resolve(prereq, &vars)?;
+                if via_pattern && self.is_intermediate(prereq_index, true) {
+                    self.nodes[prereq_index].intermediate = true;
+                }
+                self.nodes[prereq_index]
+                    .required_by
+                    .get_or_insert_with(|| (target.to_string(), rule.rule));
+                if !prerequisites.contains(&prereq_index) {
+                    prerequisites.push(prereq_index);
+                }
+
+                for &other in &before {
+                    self.wait(prereq_index, other);
+                }
+                group.push(prereq_index);
+                if serial {
+                    before.append(&mut group);
+                }
+            }
+        }
+
+        let node = &mut self.nodes[index];
+        node.rules = rules;
+        node.prerequisites = prerequisites;
+        self.stack.pop();
+        self.order.push(index);
+
+        Ok(index)
+    }
+
+    /// Make the node at `index` wait for the node at `other` to complete. Since waiting for a node
+    /// which (directly or indirectly) waits for this one would never finish, such constraints are
+    /// ignored.
+    fn wait(&mut self, index: usize, other: usize) {
+        if index == other
+            || self.nodes[index].wait_for.contains(&other)
+            || self.reaches(other, index)
+        {
+            return;
+        }
+
+        self.nodes[index].wait_for.push(other);
+    }
+
+    /// Check whether the node at `from` depends on the node at `to`, through prerequisites or
+    /// waiting.
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = vec![from];
+        while let Some(index) = stack.pop() {
+            if index == to {
+                return true;
+            }
+            if !std::mem::replace(&mut seen[index], true) {
+                let node = &self.nodes[index];
+                stack.extend(node.prerequisites.iter().chain(&node.wait_for));
+            }
+        }
+
+        false
+    }
+
+    /// Build an error describing the cycle from the node at `index` (which must be on the stack)
+    /// back to itself, where `rule` is the rule which closes the cycle.
+    fn circular_dependency(&self, index: usize, rule: &Rule) -> MakeError {
+        let start = self.stack.iter().position(|&i| i == index).unwrap();
+        let cycle = self.stack[start..]
+            .iter()
+            .chain([&index])
+            .map(|&i| self.nodes[i].target.as_str())
+            .collect::<Vec<_>>();
+
+        MakeError::new(
+            format!("Circular dependency: {}.", cycle.join(" -> ")),
+            rule.context.clone(),
+        )
+    }
+
+    /// Check whether a node is an intermediate file, which is deleted once the build is finished
+    /// (if it was made). This is a file which doesn't exist yet, and which is either declared
+    /// `.INTERMEDIATE`, or only needed by a pattern rule (`via_pattern`) without being mentioned as
+    /// a target or prerequisite of any other rule. Targets declared `.SECONDARY` are never
+    /// intermediate.
+    ///
+    /// Note that, unlike GNU make, a missing intermediate file is remade whenever a target
+    /// depending on it is considered, which also remakes that target.
+    fn is_intermediate(&self, index: usize, via_pattern: bool) -> bool {
+        let node = &self.nodes[index];
+        let target = node.target.as_str();
+        if self.makefile.secondary.contains(target) || Path::new(&node.path).exists() {
+            return false;
+        }
+        if self.makefile.intermediate.contains(target) {
+            return true;
+        }
+
+        // Special targets (e.g., `.SECONDARY`) don't count as mentioning their prerequisites.
+        via_pattern
+            && !self.rule_map.by_target.contains_key(target)
+            && !self.rule_map.rules().any(|rule| {
+                !rule
+                    .targets
+                    .iter()
+                    .any(|t| t.starts_with('.') || is_pattern(t))
+                    && rule
+                        .prerequisites
+                        .iter()
+                        .chain(&rule.order_only)
+                        .any(|p| p == target)
+            })
+    }
+
+    /// Delete the intermediate files which were made, unless just printing or touching.
+    fn delete_intermediates(&self) {
+        let args = &self.makefile.args;
+        if args.just_print || args.touch {
+            return;
+        }
+
+        let files = self
+            .order
+            .iter()
+            .map(|&i| &self.nodes[i])
+            .filter(|node| node.intermediate && !node.goal && node.state == State::Done)
+            .map(|node| node.path.as_str())
+            .filter(|path| Path::new(path).exists())
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            return;
+        }
+
+        if !args.silent && !self.makefile.silent.is_all() {
+            println!("rm {}", files.join(" "));
+        }
+        for file in files {
+            if let Err(e) = fs::remove_file(file) {
+                log_warn(
+                    format!("Failed to delete intermediate file '{file}' ({e})."),
+                    Some(&Context::new().with_target(file)),
+                );
+            }
+        }
+    }
+
+    /// Find the rules which apply to a target. Single-colon rules for the same target are merged
+    /// into one (see `merge_rules`). If the target has no explicit rules with a recipe (even an
+    /// empty one), then we also search for a pattern rule, whose recipe is used along with the
+    /// prerequisites of all of the explicit rules.
+    fn find_rules(&self, target: &str) -> Result<Vec<AppliedRule<'a>>, MakeError> {
+        let rule_map = self.rule_map;
+        let mut rules = vec![];
+        for &i in rule_map.by_target.get(target).into_iter().flatten() {
+            let rule = &rule_map.rules[i];
+            let stem = rule
+                .static_pattern
+                .as_ref()
+                .and_then(|pattern| match_pattern(pattern, target))
+                .unwrap_or_default();
+            let expand = |prerequisites| {
+                rule.expand_prerequisites(self.makefile, prerequisites, target, stem)
+            };
+            rules.push(AppliedRule {
+                rule,
+                prerequisites: expand(&rule.prerequisites)?,
+                order_only: expand(&rule.order_only)?,
+                stem: stem.to_string(),
+            });
+        }
+        if rules.len() > 1 && !rules[0].rule.double_colon {
+            rules = vec![merge_rules(rules)];
+        }
+
+        if rules
+            .iter()
+            .all(|r| r.rule.recipe.is_empty() && !r.rule.empty_recipe && !r.rule.double_colon)
+        {
+            let pattern_rule = rule_map.find_pattern_rule(self.makefile, target)?;
+            match &pattern_rule {
+                Some(applied) => self.debug(
+                    DebugFlag::Implicit,
+                    target,
+                    format!(
+                        "Using pattern rule '{}: {}' for target '{target}', with stem '{}'.",
+                        applied.rule.targets.join(" "),
+                        applied.rule.prerequisites.join(" "),
+                        applied.stem,
+                    ),
+                ),
+                None => self.debug(
+                    DebugFlag::Implicit,
+                    target,
+                    format!("No pattern rule found for target '{target}'."),
+                ),
+            }
+
+            if let Some(mut applied) = pattern_rule {
+                for explicit in rules.drain(..) {
+                    applied.prerequisites.extend(explicit.prerequisites);
+                    applied.order_only.extend(explicit.order_only);
+                }
+
+                rules.push(applied);
+            }
+        }
+
+        // Targets without any rules at all fall back to the recipe of `.DEFAULT`, if there is one.
+        if rules.is_empty() {
+            let default = rule_map.by_target.get(".DEFAULT").into_iter().flatten();
+            if let Some(rule) = default
+                .map(|&i| &rule_map.rules[i])
+                .rfind(|r| r.has_recipe())
+            {
+                if !rule.recipe.is_empty() {
+                    self.debug(
+                        DebugFlag::Implicit,
+                        target,
+                        format!("Using the recipe of '.DEFAULT' for target '{target}'."),
+                    );
+                    rules.push(AppliedRule {
+                        rule,
+                        prerequisites: vec![],
+                        order_only: vec![],
+                        stem: String::new(),
+                    });
+                }
+            }
+        }
+
+        Ok(rules)
+    }
+
+    /// Find the first pending node whose prerequisites (and any nodes it waits for) have all
+    /// completed (successfully or not).
+    fn next_ready(&self) -> Option<usize> {
+        self.order.iter().copied().find(|&i| {
+            let node = &self.nodes[i];
+            node.state == State::Pending
+                && node.prerequisites.iter().chain(&node.wait_for).all(|&p| {
+                    matches!(
+                        self.nodes[p].state,
+                        State::Done | State::Failed | State::Skipped
+                    )
+                })
+        })
+    }
+
+    /// Determine which of a node's rules need to be executed, along with the prerequisites which
+    /// are newer than the target for each. This must only be called once all of the node's
+    /// prerequisites are complete, so their modification times are final.
+    fn outdated_rules(&self, index: usize) -> Result<Vec<(&'a Rule, AutomaticVars)>, MakeError> {
+        let node = &self.nodes[index];
+        let args = &self.makefile.args;
+        let target_mtime_opt = self.mtime(index);
+        self.debug(
+            DebugFlag::Verbose,
+            &node.target,
+            format!("Considering target '{}'.", node.target),
+        );
+
+        if node.rules.is_empty() {
+            if args.is_old_file(&node.target) {
+                if !args.question {
+                    log_info(
+                        format!("Target '{}' is up to date (old).", node.target),
+                        Some(&Context::new().with_target(&node.target)),
+                    );
+                }
+            } else if target_mtime_opt.is_none() && !self.makefile.phony.contains(&node.target) {
+                // For a prerequisite, point at the rule which required it.
+                return Err(match &node.required_by {
+                    None => MakeError::new(
+                        format!("No rule to make target '{}'.", node.target),
+                        Context::new().with_target(&node.target),
+                    ),
+                    Some((target, rule)) => MakeError::new(
+                        format!(
+                            "No rule to make target '{}', required by '{target}'.",
+                            node.target
+                        ),
+                        rule.context.clone().with_target(&node.target),
+                    ),
+                });
+            }
+
+            return Ok(vec![]);
+        }
+
+        let mut outdated = vec![];
+        for applied in &node.rules {
+            // Collect the prerequisites which are newer than the target. If the target is missing
+            // (or we are making everything), then all prerequisites are considered newer.
+            let mut newer: Vec<String> = vec![];
+            for prereq in applied.prerequisites.iter().filter(|p| *p != WAIT) {
+                let prereq_index = self.by_target[prereq];
+                let path = &self.nodes[prereq_index].path;
+                let is_newer = match target_mtime_opt {
+                    None => true,
+                    Some(target_mtime) => {
+                        // A missing prereq is, by definition, more up-to-date than the target. A
+                        // prereq with the same `mtime` is also considered newer, since with coarse
+                        // timestamps, it may have been modified just after the target was made.
+                        // With `--check-hashes`, one whose contents haven't changed since the
+                        // target was made is not.
+                        args.always_make
+                            || (self.mtime(prereq_index).is_none_or(|m| m >= target_mtime)
+                                && !self
+                                    .manifest
+                                    .as_ref()
+                                    .is_some_and(|m| m.unchanged(&node.path, path)))
+                    }
+                };
+
+                if target_mtime_opt.is_some() {
+                    let comparison = if is_newer { "newer" } else { "older" };
+                    self.debug(
+                        DebugFlag::Verbose,
+                        &node.target,
+                        format!(
+                            "Prerequisite '{path}' is {comparison} than target '{}'.",
+                            node.target
+                        ),
+                    );
+                }
+                if is_newer && !newer.contains(path) {
+                    newer.push(path.clone());
+                }
+            }
+
+            // Recipes refer to prerequisites by where they were actually found.
+            let paths = |prerequisites: &Vec<String>| {
+                prerequisites
+                    .iter()
+                    .filter(|p| *p != WAIT)
+                    .map(|p| self.nodes[self.by_target[p]].path.clone())
+                    .collect::<Vec<_>>()
+            };
+
+            // Each double-colon rule is considered independently, based only on its own
+            // prerequisites, and one without any prerequisites always runs.
+            let always = applied.rule.double_colon && applied.prerequisites.is_empty();
+            if always || target_mtime_opt.is_none() || args.always_make || !newer.is_empty() {
+                let reason = if always {
+                    "it has a double-colon rule without prerequisites".to_string()
+                } else if args.always_make {
+                    "all targets are being remade".to_string()
+                } else if target_mtime_opt.is_none() {
+                    if self.makefile.phony.contains(&node.target) {
+                        "it is phony".to_string()
+                    } else {
+                        "it does not exist".to_string()
+                    }
+                } else {
+                    let names = newer.iter().map(|p| format!("'{p}'")).collect::<Vec<_>>();
+                    let verb = if names.len() == 1 { "is" } else { "are" };
+                    format!("{} {verb} newer", names.join(", "))
+                };
+                self.debug(
+                    DebugFlag::Basic,
+                    &node.target,
+                    format!("Target '{}' is out of date because {reason}.", node.target),
+                );
+
+                outdated.push((
+                    applied.rule,
+                    AutomaticVars {
+                        target: node.target.clone(),
+                        prerequisites: paths(&applied.prerequisites),
+                        newer_prerequisites: newer,
+                        order_only: paths(&applied.order_only),
+                        stem: applied.stem.clone(),
+                    },
+                ));
+            }
+        }
+
+        if outdated.is_empty() {
+            self.debug(
+                DebugFlag::Verbose,
+                &node.target,
+                format!("Target '{}' is up to date.", node.target),
+            );
+        }
+
+        Ok(outdated)
+    }
+
+    /// Log debugging information about a target, if enabled for the category.
+    fn debug(&self, flag: DebugFlag, target: &str, msg: String) {
+        if self.makefile.args.debug(flag) {
+            log_debug(msg, Some(&Context::new().with_target(target)));
+        }
+    }
+
+    /// Get the `mtime` of a node's file (which is cached). Phony targets are never considered to
+    /// exist, so they are always out of date, and always newer than anything that depends on them.
+    fn mtime(&self, index: usize) -> Option<SystemTime> {
+        let node = &self.nodes[index];
+        if self.makefile.phony.contains(&node.target) {
+            return None;
+        }
+
+        if let Some(&mtime) = self.mtimes.borrow().get(&node.path) {
+            return mtime;
+        }
+
+        // A file from the future usually means the clock is skewed (e.g., on a network filesystem),
+        // which causes targets to be remade on every run. Files given with `-W` are expected to be.
+        let mtime = get_mtime(&node.path, &self.makefile.args);
+        let args = &self.makefile.args;
+        if mtime.is_some_and(|m| m > SystemTime::now()) && !args.is_new_file(&node.path) {
+            log_warn(
+                format!(
+                    "File '{}' has a modification time in the future (clock skew?).",
+                    node.path
+                ),
+                Some(&Context::new().with_target(&node.target)),
+            );
+        }
+
+        self.mtimes.borrow_mut().insert(node.path.clone(), mtime);
+        mtime
+    }
+
+    /// Mark a node as failed. Normally, only the first error is kept (to be returned once running
+    /// jobs complete), but when keeping going, each error is logged as it happens.
+    fn fail(&mut self, index: usize, mut e: MakeError, error: &mut Option<MakeError>) {
+        let node = &mut self.nodes[index];
+        node.state = State::Failed;
+        self.mtimes.get_mut().remove(&node.path);
+        if e.context.target.is_none() {
+            e.context.target = Some(node.target.clone());
+        }
+
+        if self.makefile.args.keep_going {
+            log_error(&e.msg, Some(&e.context));
+        } else if error.is_none() {
+            *error = Some(e);
+        }
+    }
+
+    /// Record how long the recipes of a node took to run, with `--stats`. Targets without any
+    /// recipe lines are left out.
+    fn record_time(&mut self, index: usize, elapsed: Duration) {
+        let node = &self.nodes[index];
+        if self.makefile.args.stats && node.rules.iter().any(|r| !r.rule.recipe.is_empty()) {
+            self.timings.push((node.target.clone(), elapsed));
+        }
+    }
+
+    /// Mark a node as skipped, because one of its prerequisites failed.
+    fn skip(&mut self, index: usize) {
+        let node = &mut self.nodes[index];
+        node.state = State::Skipped;
+
+        log_info(
+            format!("Target '{}' not remade because of errors.", node.target),
+            Some(&Context::new().with_target(&node.target)),
+        );
+    }
+
+    /// Mark a node as complete. If its recipe was executed, its file may have changed, so its
+    /// `mtime` must be checked again.
+    fn finish(&mut self, index: usize, executed: bool) {
+        // Record the prerequisites' hashes, now that the target is made from them (unless nothing
+        // was actually made).
+        let args = &self.makefile.args;
+        if executed && !args.just_print && !args.question {
+            if let Some(manifest) = &mut self.manifest {
+                let node = &self.nodes[index];
+                let prereqs = node
+                    .rules
+                    .iter()
+                    .flat_map(|applied| &applied.prerequisites)
+                    .filter(|p| *p != WAIT)
+                    .map(|p| self.nodes[self.by_target[p]].path.clone())
+                    .collect::<Vec<_>>();
+                manifest.invalidate(&node.path);
+                manifest.record(&node.path, &prereqs);
+            }
+        }
+
+        let node = &mut self.nodes[index];
+        node.state = State::Done;
+        if executed {
+            self.mtimes.get_mut().remove(&node.path);
+        } else {
+            // Only files we made are deleted as intermediate files.
+            node.intermediate = false;
+        }
+
+        if node.goal && !executed && !node.rules.is_empty() {
+            log_info(
+                format!("Target '{}' is up to date.", node.target),
+                Some(&Context::new().with_target(&node.target)),
+            );
+        }
+    }
+}
+
+/// Merge the single-colon rules for a target into one, using the recipe of the last rule which has
+/// one. As in GNU make, its prerequisites come first, followed by those of the other rules in the
+/// order they were defined.
+fn merge_rules(rules: Vec<AppliedRule>) -> AppliedRule {
+    let main = rules
+        .iter()
+        .rposition(|applied| applied.rule.has_recipe())
+        .unwrap_or(0);
+
+    let mut merged = rules[main].clone();
+    for (i, applied) in rules.into_iter().enumerate() {
+        if i != main {
+            merged.prerequisites.extend(applied.prerequisites);
+            merged.order_only.extend(applied.order_only);
+        }
+    }
+    merged
+}
+
+/// Describe how long the slowest targets (at most `STATS_TARGETS` of them) took to make, slowest
+/// first, followed by the total time of the build.
+fn stats_report(mut timings: Vec<(String, Duration)>, total: Duration) -> Vec<String> {
+    let count = timings.len();
+    timings.sort_by(|(a, a_time), (b, b_time)| b_time.cmp(a_time).then_with(|| a.cmp(b)));
+
+    let mut lines = timings
+        .iter()
+        .take(STATS_TARGETS)
+        .map(|(target, time)| format!("Target '{target}' took {:.3}s.", time.as_secs_f64()))
+        .collect::<Vec<_>>();
+    let plural = if count == 1 { "" } else { "s" };
+    lines.push(format!(
+        "Made {count} target{plural} in {:.3}s.",
+        total.as_secs_f64()
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_report() {
+        let timings = (0..12)
+            .map(|i| (format!("t{i}"), Duration::from_millis(i * 100)))
+            .chain([("a".to_string(), Duration::from_millis(1100))])
+            .collect();
+        let report = stats_report(timings, Duration::from_millis(12345));
+        assert_eq!(report.len(), STATS_TARGETS + 1);
+        assert_eq!(report[0], "Target 'a' took 1.100s.");
+        assert_eq!(report[1], "Target 't11' took 1.100s.");
+        assert_eq!(report[2], "Target 't10' took 1.000s.");
+        assert_eq!(report[9], "Target 't3' took 0.300s.");
+        assert_eq!(report[10], "Made 13 targets in 12.345s.");
+
+        let report = stats_report(vec![], Duration::from_millis(5));
+        assert_eq!(report, ["Made 0 targets in 0.005s."]);
+    }
+}
@@ -0,0 +1,184 @@
+//! Helpers for invoking the shell, shared by recipe execution and the `$(shell ...)` function.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+use super::expand::Expander;
+
+/// Build a `Command` which runs `script` using the given `shell` and (whitespace-separated)
+/// `shell_flags`. Since `cmd.exe` does not parse its command line like other programs, the script
+/// is passed to it verbatim, rather than quoted as a single argument.
+pub fn command(shell: &str, shell_flags: &str, script: &str) -> Command {
+    let mut command = Command::new(shell);
+    command.args(shell_flags.split_whitespace());
+
+    #[cfg(windows)]
+    if is_cmd(shell) {
+        std::os::windows::process::CommandExt::raw_arg(&mut command, script);
+        return command;
+    }
+
+    command.arg(script);
+    command
+}
+
+/// Get the flags to run the shell with. Unless `.SHELLFLAGS` has been set, these follow `shell`,
+/// so that setting `SHELL` to `sh` on Windows (or `cmd` elsewhere) works as expected.
+pub fn flags(expander: &mut Expander, shell: &str) -> Result<String, String> {
+    if expander.expand("$(origin .SHELLFLAGS)")? == "default" {
+        return Ok(default_flags(shell).to_string());
+    }
+
+    expander.expand("$(.SHELLFLAGS)")
+}
+
+/// The flags which make `shell` run a command: `/c` for `cmd.exe`, and `-c` for everything else.
+fn default_flags(shell: &str) -> &'static str {
+    if is_cmd(shell) {
+        "/c"
+    } else {
+        "-c"
+    }
+}
+
+/// Check whether `shell` is the Windows command interpreter.
+fn is_cmd(shell: &str) -> bool {
+    Path::new(shell.trim())
+        .file_stem()
+        .is_some_and(|stem| stem.eq_ignore_ascii_case("cmd"))
+}
+
+/// Check that `shell` names an existing program, so that a bad `SHELL` can be reported clearly,
+/// rather than by the error from spawning it.
+pub fn validate(shell: &str) -> Result<(), String> {
+    if shell.trim().is_empty() {
+        return Err("SHELL is empty.".to_string());
+    }
+
+    match find_program(shell) {
+        Some(_) => Ok(()),
+        None => Err(format!("SHELL '{shell}' not found.")),
+    }
+}
+
+/// Find a program like `Command` does: a name containing a path separator is used as is, and
+/// otherwise it is searched for in the directories of `PATH`.
+pub fn find_program(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    let candidates = |path: PathBuf| {
+        // On Windows, the `.exe` extension is implied.
+        let exe = cfg!(windows).then(|| path.with_extension("exe"));
+        [Some(path), exe].into_iter().flatten()
+    };
+
+    if path.components().count() > 1 {
+        return candidates(path.to_path_buf()).find(|p| p.is_file());
+    }
+
+    env::split_paths(&env::var_os("PATH")?)
+        .flat_map(|dir| candidates(dir.join(name)))
+        .find(|p| p.is_file())
+}
+
+/// Get the `MAKELEVEL` for child processes, which is one deeper than our own.
+pub fn child_makelevel(makelevel: &str) -> String {
+    (makelevel.trim().parse::<usize>().unwrap_or(0) + 1).to_string()
+}
+
+/// Check whether a (raw) recipe line invokes a sub-make through `$(MAKE)` or `${MAKE}`.
+pub fn is_recursive(line: &str) -> bool {
+    line.contains("$(MAKE)") || line.contains("${MAKE}")
+}
+
+/// Get a numeric exit code from an `ExitStatus`, following the shell convention of `128 + N` for a
+/// process killed by signal `N`.
+pub fn exit_code(status: &ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+
+    status.code().unwrap_or(1)
+}
+
+/// Describe why `command` failed, or return `None` if it succeeded. Processes killed by a signal
+/// are reported with the signal's name (where known) and number.
+pub fn describe_failure(command: &str, status: &ExitStatus) -> Option<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.signal() {
+            let core_dumped = if status.core_dumped() {
+                ", core dumped"
+            } else {
+                ""
+            };
+            return Some(match signal_name(signal) {
+                Some(name) => format!(
+                    "Command `{command}` was killed by {name} (signal {signal}{core_dumped})."
+                ),
+                None => format!("Command `{command}` was killed by signal {signal}{core_dumped}."),
+            });
+        }
+    }
+
+    match status.code() {
+        Some(0) => None,
+        Some(code) => Some(format!("Command `{command}` failed with code {code}.")),
+        None => Some(format!("Command `{command}` failed.")),
+    }
+}
+
+/// Describe a signal by its name (where known), or otherwise its number.
+pub fn describe_signal(signal: i32) -> String {
+    #[cfg(unix)]
+    if let Some(name) = signal_name(signal) {
+        return name.to_string();
+    }
+
+    format!("signal {signal}")
+}
+
+/// Get the name of a signal, for the common ones.
+#[cfg(unix)]
+fn signal_name(signal: i32) -> Option<&'static str> {
+    Some(match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_flags() {
+        assert_eq!(default_flags("/bin/sh"), "-c");
+        assert_eq!(default_flags("bash"), "-c");
+        assert_eq!(default_flags("cmd"), "/c");
+        assert_eq!(default_flags("CMD.EXE"), "/c");
+    }
+}
@@ -0,0 +1,78 @@
+//! Helpers for treating file names as paths in a platform-aware way. Makefiles always use `/` as a
+//! separator, but on Windows, file names may also use `\` and start with a drive letter (e.g.,
+//! `C:\src\main.c`), which must not be mistaken for the `:` of a rule.
+
+/// The separator used in lists of directories, such as `VPATH`. This is `;` on Windows, where `:`
+/// appears in drive letters.
+pub const LIST_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+
+/// Check if `ch` separates path components. On Windows, both `/` and `\` do.
+pub fn is_separator(ch: char) -> bool {
+    ch == '/' || (cfg!(windows) && ch == '\\')
+}
+
+/// Split a path into its components.
+pub fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split(is_separator)
+}
+
+/// Check if two file names refer to the same path, treating all separators as equal.
+pub fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.chars()
+            .zip(b.chars())
+            .all(|(x, y)| x == y || (is_separator(x) && is_separator(y)))
+}
+
+/// Check if `path` is absolute. On Windows, this is a path starting with a separator or a drive.
+pub fn is_absolute(path: &str) -> bool {
+    path.starts_with(is_separator) || (cfg!(windows) && drive_len(path) > 0)
+}
+
+/// Check if the `:` at byte index `i` of `line` is part of a Windows drive letter (e.g., `C:\`),
+/// rather than a separator in its own right. This only applies on Windows.
+pub fn is_drive_colon(line: &str, i: usize) -> bool {
+    cfg!(windows) && has_drive_colon(line, i)
+}
+
+/// The length of the drive prefix (e.g., `C:`) of a path, or `0` if there is none.
+fn drive_len(path: &str) -> usize {
+    if has_drive_colon(path, 1) {
+        2
+    } else {
+        0
+    }
+}
+
+/// Check for a drive letter ending at byte index `i`: a single letter at the start of a word,
+/// followed by `:` and a separator (either `/` or `\`).
+fn has_drive_colon(line: &str, i: usize) -> bool {
+    let bytes = line.as_bytes();
+    i >= 1
+        && bytes.get(i) == Some(&b':')
+        && bytes[i - 1].is_ascii_alphabetic()
+        && (i == 1 || bytes[i - 2].is_ascii_whitespace())
+        && matches!(bytes.get(i + 1), Some(b'/' | b'\\'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_drive_colon() {
+        assert!(has_drive_colon(r"C:\src\main.o: main.c", 1));
+        assert!(has_drive_colon("all: C:/bin/tool", 6));
+        assert!(!has_drive_colon(r"C:\src\main.o: main.c", 13));
+        assert!(!has_drive_colon("all: main.c", 3));
+        assert!(!has_drive_colon("ab:/x", 2));
+        assert!(!has_drive_colon("a: b", 1));
+    }
+
+    #[test]
+    fn test_same() {
+        assert!(same("a/b", "a/b"));
+        assert!(!same("a/b", "a/c"));
+        assert_eq!(same(r"a\b", "a/b"), cfg!(windows));
+    }
+}
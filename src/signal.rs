@@ -0,0 +1,127 @@
+//! Handling of interrupts (`SIGINT` and `SIGTERM`). Once `install` has been called, each recipe is
+//! run in its own process group, and the handler forwards any interrupt to the process groups of
+//! all running recipes, so that every process they spawned is terminated (rather than orphaned).
+//! The scheduler then stops starting new jobs, and the interrupt is reported as an error once the
+//! running ones have finished. Note that recipes are then not in the terminal's foreground process
+//! group, so they can't read from the terminal.
+//!
+//! This module is a no-op on platforms other than Unix.
+
+use std::process::Child;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+/// The maximum number of running recipes whose process groups can be tracked. Recipes beyond this
+/// are still run, but interrupts are not forwarded to them.
+#[cfg(unix)]
+const MAX_TRACKED: usize = 256;
+
+/// Whether the handler has been installed.
+#[cfg(unix)]
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// The first signal received, or `0` if none has been.
+#[cfg(unix)]
+static PENDING: AtomicI32 = AtomicI32::new(0);
+
+/// The process group IDs of running recipes, with `0` marking a free slot. Since this is accessed
+/// from the signal handler, it must be lock-free.
+#[cfg(unix)]
+static GROUPS: [AtomicI32; MAX_TRACKED] = [const { AtomicI32::new(0) }; MAX_TRACKED];
+
+#[cfg(unix)]
+extern "C" fn handle(signal: libc::c_int) {
+    let _ = PENDING.compare_exchange(0, signal, Ordering::SeqCst, Ordering::SeqCst);
+    for group in &GROUPS {
+        let pgid = group.load(Ordering::SeqCst);
+        if pgid > 0 {
+            // SAFETY: `kill` is async-signal-safe.
+            unsafe { libc::kill(-pgid, signal) };
+        }
+    }
+}
+
+/// Install the handler for `SIGINT` and `SIGTERM`. This should only be called by programs which
+/// exit once an interrupt is reported, such as the `omake` binary.
+pub fn install() {
+    #[cfg(unix)]
+    {
+        for signal in [libc::SIGINT, libc::SIGTERM] {
+            // SAFETY: The handler only touches atomics and calls `kill`.
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                action.sa_sigaction = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+                action.sa_flags = libc::SA_RESTART;
+                libc::sigemptyset(&mut action.sa_mask);
+                libc::sigaction(signal, &action, std::ptr::null_mut());
+            }
+        }
+
+        INSTALLED.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Whether the handler has been installed, in which case recipes should be run in their own
+/// process group (see `Tracked`).
+pub fn installed() -> bool {
+    #[cfg(unix)]
+    return INSTALLED.load(Ordering::SeqCst);
+
+    #[cfg(not(unix))]
+    false
+}
+
+/// The signal which interrupted us, if any.
+pub fn pending() -> Option<i32> {
+    #[cfg(unix)]
+    return Some(PENDING.load(Ordering::SeqCst)).filter(|&s| s != 0);
+
+    #[cfg(not(unix))]
+    None
+}
+
+/// A running recipe whose process group receives any interrupts, until this is dropped. The child
+/// must have been spawned as the leader of its own process group.
+pub struct Tracked {
+    #[cfg(unix)]
+    slot: Option<usize>,
+}
+
+impl Tracked {
+    pub fn new(child: &Child) -> Self {
+        #[cfg(unix)]
+        {
+            let Ok(pgid) = i32::try_from(child.id()) else {
+                return Self { slot: None };
+            };
+            let slot = GROUPS.iter().position(|group| {
+                group
+                    .compare_exchange(0, pgid, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+            });
+
+            // An interrupt may have arrived just before the child was tracked.
+            if let Some(signal) = pending() {
+                // SAFETY: Signalling a process group we own is always safe.
+                unsafe { libc::kill(-pgid, signal) };
+            }
+
+            Self { slot }
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = child;
+            Self {}
+        }
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(slot) = self.slot {
+            GROUPS[slot].store(0, Ordering::SeqCst);
+        }
+    }
+}
@@ -12,10 +12,19 @@ use std::collections::HashMap;
 use lazy_static::lazy_static;
 
 const BAD_VARIABLE_CHARS: [char; 3] = [':', '#', '='];
-const DEFAULT_SUFFIXES: [&str; 13] = [
-    ".C", ".F", ".S", ".c", ".cc", ".cpp", ".def", ".f", ".m", ".mod", ".p", ".r", ".s",
+/// The suffixes which are known for suffix rules (e.g., `.c.o:`) until changed using `.SUFFIXES`.
+pub const DEFAULT_SUFFIXES: [&str; 35] = [
+    ".out", ".a", ".ln", ".o", ".c", ".cc", ".C", ".cpp", ".p", ".f", ".F", ".m", ".r", ".y", ".l",
+    ".ym", ".yl", ".s", ".S", ".mod", ".sym", ".def", ".h", ".info", ".dvi", ".tex", ".texinfo",
+    ".texi", ".txinfo", ".w", ".ch", ".web", ".sh", ".elc", ".el",
 ];
 
+/// The default `SHELL`, which is `cmd.exe` on Windows.
+pub const DEFAULT_SHELL: &str = if cfg!(windows) { "cmd.exe" } else { "/bin/sh" };
+
+/// The default `.SHELLFLAGS` for `DEFAULT_SHELL`.
+const DEFAULT_SHELLFLAGS: &str = if cfg!(windows) { "/c" } else { "-c" };
+
 /// List of variables where setting the value to blank means to reset it to the default value.
 const BLANK_MEANS_DEFAULT_VARS: [&str; 1] = [".RECIPEPREFIX"];
 
@@ -26,7 +35,7 @@ lazy_static! {
     static ref DEFAULT_VARS: HashMap<String, String> = HashMap::from(
         [
             (".RECIPEPREFIX", "\t"),
-            (".SHELLFLAGS", "-c"),
+            (".SHELLFLAGS", DEFAULT_SHELLFLAGS),
             ("AR", "ar"),
             ("ARFLAGS", "rv"),
             ("AS", "as"),
@@ -101,10 +110,55 @@ const DEFAULT_RECURSIVE_VARS: [(&str, &str); 36] = [
 /// Represents the "raw" environment coming from the OS.
 pub type Env = HashMap<String, String>;
 
+/// Where a variable's value came from, which determines whether assignments in the makefile may
+/// replace it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Origin {
+    /// Not defined at all.
+    Undefined,
+    /// A builtin default, such as `CC`.
+    Default,
+    /// Inherited from the environment.
+    Environment,
+    /// Assigned in a makefile.
+    File,
+    /// Assigned on the command line (e.g., `make CC=clang`), which takes precedence over any
+    /// assignment in the makefile.
+    CommandLine,
+    /// Assigned in a makefile with the `override` directive, which takes precedence over the
+    /// command line.
+    Override,
+    /// An automatic variable (e.g., `$@`), set while a recipe is expanded.
+    Automatic,
+}
+
+impl Origin {
+    /// The name of the origin, as reported by `$(origin ...)`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Origin::Undefined => "undefined",
+            Origin::Default => "default",
+            Origin::Environment => "environment",
+            Origin::File => "file",
+            Origin::CommandLine => "command line",
+            Origin::Override => "override",
+            Origin::Automatic => "automatic",
+        }
+    }
+
+    /// Check whether an assignment from `self` may replace a value from `existing`. Values from
+    /// the command line (or `override`) may only be replaced by the command line or `override`.
+    pub fn may_replace(&self, existing: Origin) -> bool {
+        matches!(self, Origin::CommandLine | Origin::Override)
+            || !matches!(existing, Origin::CommandLine | Origin::Override)
+    }
+}
+
 #[derive(Debug)]
 pub struct Var {
     pub value: String,
     pub recursive: bool,
+    pub origin: Origin,
 }
 
 /// This wraps a `HashMap` and a default value, providing an easy way to get variables, handling
@@ -127,26 +181,29 @@ impl Vars {
             blank: Var {
                 value: "".to_string(),
                 recursive: false,
+                origin: Origin::Undefined,
             },
         };
 
         // Set default vars.
         for (k, v) in DEFAULT_VARS.iter() {
-            vars.set(k, v, false).unwrap();
+            vars.set_with_origin(k, v, false, Origin::Default).unwrap();
         }
 
         // Set default recursive vars.
         for (k, v) in DEFAULT_RECURSIVE_VARS {
-            vars.set(k, v, true).unwrap();
+            vars.set_with_origin(k, v, true, Origin::Default).unwrap();
         }
 
-        // Set `SHELL` to `/bin/sh` by default.
-        vars.set("SHELL", "/bin/sh", false).unwrap();
+        // Set `SHELL` to the platform's default shell.
+        vars.set_with_origin("SHELL", DEFAULT_SHELL, false, Origin::Default)
+            .unwrap();
 
         // Set default `SUFFIXES` and `.SUFFIXES`.
-        vars.set("SUFFIXES", &DEFAULT_SUFFIXES.join(" "), false)
+        let suffixes = DEFAULT_SUFFIXES.join(" ");
+        vars.set_with_origin("SUFFIXES", &suffixes, false, Origin::Default)
             .unwrap();
-        vars.set(".SUFFIXES", &DEFAULT_SUFFIXES.join(" "), false)
+        vars.set_with_origin(".SUFFIXES", &suffixes, false, Origin::Default)
             .unwrap();
 
         // Use `set` to initialize data.
@@ -157,6 +214,14 @@ impl Vars {
         vars
     }
 
+    /// Remove the builtin variables (such as `CC`) which have not been replaced, for
+    /// `--no-builtin-variables`. `SHELL` and the special variables starting with a `.` (such as
+    /// `.RECIPEPREFIX`) are kept, since they control how makefiles are parsed and run.
+    pub fn remove_builtins(&mut self) {
+        self.map
+            .retain(|k, var| var.origin != Origin::Default || k == "SHELL" || k.starts_with('.'));
+    }
+
     /// Public interface for getting variables. For unknown keys, the `blank` object is returned. We
     /// should try to keep this interface as fast/simple as possible since it's used far more often
     /// than `set` (e.g., used for each line to check for recipe prefix).
@@ -169,8 +234,29 @@ impl Vars {
         }
     }
 
-    /// Public interface for setting variables.
+    /// Iterate over the defined variables, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Var)> {
+        self.map.iter().map(|(k, var)| (k.as_str(), var))
+    }
+
+    /// Check whether a variable is defined (even if its value is empty).
+    pub fn is_defined(&self, k: impl AsRef<str>) -> bool {
+        self.map.contains_key(k.as_ref().trim())
+    }
+
+    /// Public interface for setting variables, as if assigned in a makefile.
     pub fn set<S: Into<String>>(&mut self, k: S, v: S, recursive: bool) -> Result<(), String> {
+        self.set_with_origin(k, v, recursive, Origin::File)
+    }
+
+    /// Set a variable, recording where its value came from.
+    pub fn set_with_origin<S: Into<String>>(
+        &mut self,
+        k: S,
+        v: S,
+        recursive: bool,
+        origin: Origin,
+    ) -> Result<(), String> {
         let k = k.into().trim().to_string();
         let mut v = v.into();
 
@@ -194,6 +280,7 @@ impl Vars {
             Var {
                 value: v,
                 recursive,
+                origin,
             },
         );
         Ok(())
@@ -215,6 +302,7 @@ impl From<Env> for Vars {
                 Var {
                     value: v,
                     recursive: false,
+                    origin: Origin::Environment,
                 },
             );
         }
@@ -232,7 +320,10 @@ mod tests {
         let vars = Vars::new([("A", "B")]);
         assert_eq!(vars.get("A").value, "B");
         assert_eq!(vars.get("B").value, "");
+        #[cfg(unix)]
         assert_eq!(vars.get("SHELL").value, "/bin/sh");
+        #[cfg(windows)]
+        assert_eq!(vars.get("SHELL").value, "cmd.exe");
         assert_eq!(
             vars.get("COMPILE.c").value,
             "$(CC) $(CFLAGS) $(CPPFLAGS) $(TARGET_ARCH) -c"
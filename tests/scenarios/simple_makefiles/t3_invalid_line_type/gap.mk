@@ -0,0 +1,7 @@
+a:
+	echo one
+
+# Blank lines and comments may appear among the recipe lines.
+	echo two
+
+    echo three
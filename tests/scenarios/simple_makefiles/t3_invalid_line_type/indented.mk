@@ -0,0 +1,4 @@
+  VALUE = ok
+
+a:
+	echo $(VALUE)
@@ -2,7 +2,33 @@ crate::system_test_cases!(
     {
         args: &[],
         expected_stdout: "",
-        expected_stderr: "make: ERROR [Makefile] | Invalid line type.\n  |\n2 |   echo \"bad indentation on this line is intentional\" > a\n  | \n\n",
+        expected_stderr: "make: ERROR [Makefile] | Missing separator (did you mean TAB instead of spaces?).\n  |\n2 |   echo \"bad indentation on this line is intentional\" > a\n  | \n\n",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "prefix.mk"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [prefix.mk] | Missing separator (did you mean `>` instead of spaces?).\n  |\n3 |   echo \"bad indentation\" > a\n  | \n\n",
+        expected_files: &[],
+    },
+    {
+        // Lines indented with spaces are fine outside of a rule.
+        args: &["-f", "indented.mk"],
+        expected_stdout: "echo ok\nok\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // The rule continues past blank lines and comments, so the line is still reported.
+        args: &["-f", "gap.mk"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [gap.mk] | Missing separator (did you mean TAB instead of spaces?).\n  |\n7 |     echo three\n  | \n\n",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "orphan.mk"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [orphan.mk] | Recipe commences before first target.\n  |\n1 | \techo orphan\n  | \n\n",
         expected_files: &[],
     },
 );
@@ -0,0 +1,4 @@
+	echo orphan
+
+a:
+	echo a
@@ -0,0 +1,3 @@
+.RECIPEPREFIX = >
+a:
+  echo "bad indentation" > a
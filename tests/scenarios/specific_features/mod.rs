@@ -1,4 +1,86 @@
+mod t10_circular_dependency;
+mod t11_wildcard;
+mod t12_shell;
+mod t13_output_sync;
+mod t14_makeflags;
+mod t15_keep_going;
+mod t16_jobserver;
+mod t17_conditionals;
+mod t18_include;
+mod t19_directory_modifiers;
 mod t1_custom_recipe_prefix;
+mod t20_double_colon;
+mod t21_dry_run;
+mod t22_force_modifier;
+mod t23_touch;
+mod t24_question;
+mod t25_vpath;
+mod t26_assignment;
+mod t27_target_specific_vars;
+mod t28_pattern_specific_vars;
+mod t29_ignore;
 mod t2_always_make;
+mod t30_silent;
+mod t31_silent_flag;
+mod t32_suffix_rules;
+mod t33_exit_status;
+mod t34_delete_on_error;
+mod t35_json_logs;
+mod t36_recursive_make;
+mod t37_default_goal;
+mod t38_default_rule;
+mod t39_command_line_vars;
 mod t3_old_files;
+mod t40_override;
+mod t41_diagnostics;
+mod t42_one_shell;
+mod t43_exit_codes;
+mod t44_mtime_cache;
+mod t45_mtime_precision;
+#[cfg(unix)]
+mod t46_symlinks;
+mod t47_call;
+mod t48_define;
+mod t49_eval;
 mod t4_just_print;
+mod t50_debug;
+mod t51_trace;
+mod t52_prefix_output;
+mod t53_assume_globs;
+mod t54_wait;
+#[cfg(unix)]
+mod t55_interrupt;
+mod t56_directory;
+mod t57_makefile_list;
+mod t58_line_continuations;
+mod t59_include_once;
+mod t5_jobs;
+mod t60_build_once;
+mod t61_shell;
+mod t62_empty_recipe;
+mod t63_semicolon_recipes;
+mod t64_comments;
+mod t65_no_builtins;
+mod t66_print_data_base;
+mod t67_makecmdgoals;
+mod t68_second_expansion;
+mod t69_intermediate;
+mod t6_automatic_variables;
+mod t70_multiple_makefiles;
+mod t71_print_directory;
+mod t72_export;
+mod t73_flavor_value;
+mod t74_static_pattern;
+mod t75_warn_undefined;
+mod t76_max_load;
+mod t77_check_hashes;
+mod t78_file;
+mod t79_no_recipe;
+mod t7_phony;
+mod t80_blank_recipe_lines;
+mod t81_expr;
+mod t82_stdin;
+mod t83_print_graph;
+mod t84_overriding_recipes;
+mod t8_pattern_rules;
+mod t9_order_only;
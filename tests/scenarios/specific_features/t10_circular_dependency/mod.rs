@@ -0,0 +1,6 @@
+crate::system_test_cases!({
+    args: &[],
+    expected_stdout: "",
+    expected_stderr: "make: ERROR [Makefile] | Circular dependency: a -> b -> c -> a.\n  |\n7 | c: a\n  | \n\n",
+    expected_files: &[],
+});
@@ -0,0 +1,6 @@
+crate::system_test_cases!({
+    args: &[],
+    expected_stdout: "echo \"a.c b.c | sub/c.c Makefile | \"\na.c b.c | sub/c.c Makefile | \n",
+    expected_stderr: "",
+    expected_files: &[],
+});
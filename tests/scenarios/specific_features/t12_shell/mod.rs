@@ -0,0 +1,6 @@
+crate::system_test_cases!({
+    args: &[],
+    expected_stdout: "echo \"one two 3 in recipe\"\none two 3 in recipe\n",
+    expected_stderr: "",
+    expected_files: &[],
+});
@@ -0,0 +1,11 @@
+all: a b
+
+a:
+	echo a1
+	sleep 0.3
+	echo a2
+
+b:
+	sleep 0.1
+	echo b1
+	sleep 0.5
@@ -0,0 +1,23 @@
+const OMAKE: &str = "../../../../target/debug/omake --no-print-directory";
+const FILTER: &str = "grep '^[ab]' | cut -c1 | uniq | sort";
+
+crate::system_test_cases!(
+    {
+        args: &["long"],
+        expected_stdout: &format!("{OMAKE} -j2 --output-sync=target -f jobs.mk | {FILTER}\na\nb\n"),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["short"],
+        expected_stdout: &format!("{OMAKE} -j2 -O -f jobs.mk | {FILTER}\na\nb\n"),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["attached"],
+        expected_stdout: &format!("{OMAKE} -j2 -Otarget -f jobs.mk | {FILTER}\na\nb\n"),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,14 @@
+crate::system_test_cases!({
+    args: &["-i"],
+    expected_stdout: concat!(
+        "echo \"outer: $MAKEFLAGS\"\n",
+        "outer: i\n",
+        "../../../../target/debug/omake --no-print-directory -f sub.mk\n",
+        "echo \"inner: $MAKEFLAGS\"\n",
+        "inner: i --no-print-directory\n",
+        "MAKEFLAGS=n ../../../../target/debug/omake --no-print-directory -f sub.mk -j2\n",
+        "echo \"inner: $MAKEFLAGS\"\n",
+    ),
+    expected_stderr: "",
+    expected_files: &[],
+});
@@ -0,0 +1,2 @@
+sub:
+	echo "inner: $$MAKEFLAGS"
@@ -0,0 +1,29 @@
+crate::system_test_cases!(
+    {
+        args: &["-k", "all", "later"],
+        expected_stdout: "echo good\ngood\nfalse\necho later\nlater\n",
+        expected_stderr: concat!(
+            "make: ERROR [Makefile] | Command `false` failed with code 1.\n",
+            "  |\n",
+            "6 | bad:\n",
+            "  | \n",
+            "\n",
+            "make: INFO  | Target 'dependent' not remade because of errors.\n",
+            "make: INFO  | Target 'all' not remade because of errors.\n",
+            "make: ERROR | Failed to make targets: bad.\n",
+        ),
+        expected_files: &[],
+    },
+    {
+        args: &["all", "later"],
+        expected_stdout: "echo good\ngood\nfalse\n",
+        expected_stderr: concat!(
+            "make: ERROR [Makefile] | Command `false` failed with code 1.\n",
+            "  |\n",
+            "6 | bad:\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,4 @@
+all: a b c d
+
+a b c d:
+	echo $@ >> log
@@ -0,0 +1,36 @@
+const STDOUT: &str = concat!(
+    "echo \"$MAKEFLAGS\" | sed \"s/[0-9][0-9]*,[0-9][0-9]*/R,W/\" > flags\n",
+    "../../../../target/debug/omake --no-print-directory -f sub.mk | sort\n",
+    "echo fast >> log\n",
+    "echo slow >> log\n",
+    "for i in 1 2 3 4 5 6 7 8 9 10; do test -s log && break; sleep 0.1; done\n",
+);
+
+crate::system_test_cases!(
+    {
+        // The sub-make joins the pool, so it can run `slow` and `fast` at once.
+        args: &["-j2"],
+        expected_stdout: STDOUT,
+        expected_stderr: "",
+        expected_files: &[("flags", "-j2 --jobserver-auth=R,W\n"), ("log", "fast\nslow\n")],
+    },
+    {
+        args: &[],
+        expected_stdout: STDOUT,
+        expected_stderr: "",
+        expected_files: &[("flags", "\n"), ("log", "slow\nfast\n")],
+    },
+    {
+        // Both tokens go to the sub-makes, so each of them is left with only its implicit token,
+        // which its jobs must be able to take turns with rather than all waiting on the pool.
+        args: &["-j2", "-f", "pair.mk"],
+        expected_stdout: concat!(
+            "../../../../target/debug/omake --no-print-directory -f four.mk > /dev/null\n",
+            "../../../../target/debug/omake --no-print-directory -f four.mk > /dev/null\n",
+            "sort log > sorted\n",
+            "rm log\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[("sorted", "a\na\nb\nb\nc\nc\nd\nd\n")],
+    },
+);
@@ -0,0 +1,10 @@
+OMAKE = ../../../../target/debug/omake
+
+# Both sub-makes share the top-level pool of two slots, so each must be able to hand its implicit
+# slot to its own waiting jobs.
+all: one two
+	sort log > sorted
+	rm log
+
+one two:
+	$(OMAKE) --no-print-directory -f four.mk > /dev/null
@@ -0,0 +1,10 @@
+all: slow fast
+
+# `slow` holds its job slot until `fast` has logged (for up to a second), so with a second slot
+# from the pool, `fast` is logged first however long each takes to start.
+slow:
+	for i in 1 2 3 4 5 6 7 8 9 10; do test -s log && break; sleep 0.1; done
+	echo slow >> log
+
+fast:
+	echo fast >> log
@@ -0,0 +1,3 @@
+ifdef FOO
+all:
+	echo foo
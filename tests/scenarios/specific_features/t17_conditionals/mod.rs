@@ -0,0 +1,32 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "echo \"app.bin debug-mode\"\napp.bin debug-mode\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "missing_endif.mk"],
+        expected_stdout: "",
+        expected_stderr: concat!(
+            "make: ERROR [missing_endif.mk] | Missing 'endif'.\n",
+            "  |\n",
+            "1 | ifdef FOO\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "extra_endif.mk"],
+        expected_stdout: "",
+        expected_stderr: concat!(
+            "make: ERROR [extra_endif.mk] | Extraneous 'endif'.\n",
+            "  |\n",
+            "3 | endif\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,7 @@
+# These assign variables named like the directives, rather than using them.
+include = nope.mk
+sinclude := nope.mk
+vpath += nope
+
+all:
+	echo $(include) $(sinclude) $(vpath)
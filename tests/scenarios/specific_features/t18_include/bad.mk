@@ -0,0 +1,4 @@
+all:
+	echo all
+
+include nope.mk
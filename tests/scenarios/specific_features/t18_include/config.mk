@@ -0,0 +1,2 @@
+GREETING = hello
+PLACE = config
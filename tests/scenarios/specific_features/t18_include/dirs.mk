@@ -0,0 +1,5 @@
+# `common.mk` is only found through `-I`.
+include common.mk
+
+all:
+	@echo $(COMMON)
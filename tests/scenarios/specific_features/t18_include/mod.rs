@@ -0,0 +1,56 @@
+use omake::makefile::INCLUDE_DIRS;
+
+/// List the paths searched for an included makefile: the path itself, then the path in each of
+/// `dirs` (given with `-I`) and the default include directories, which depend on the platform.
+fn searched(path: &str, dirs: &[&str]) -> String {
+    let candidates = dirs
+        .iter()
+        .chain(INCLUDE_DIRS)
+        .map(|dir| format!("{dir}/{path}"));
+    std::iter::once(path.to_string())
+        .chain(candidates)
+        .map(|candidate| format!("`{candidate}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "echo a\na\necho b\nb\necho \"hello from parts\"\nhello from parts\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "bad.mk"],
+        expected_stdout: "",
+        expected_stderr: &format!(
+            "make: ERROR [bad.mk] | Could not find makefile `nope.mk` (searched {}).\n  |\n\
+             4 | include nope.mk\n  | \n\n",
+            searched("nope.mk", &[]),
+        ),
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "assign.mk"],
+        expected_stdout: "echo nope.mk nope.mk nope\nnope.mk nope.mk nope\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "dirs.mk", "-I", "missing", "-I", "shared/"],
+        expected_stdout: "from shared\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "dirs.mk", "-I", "missing"],
+        expected_stdout: "",
+        expected_stderr: &format!(
+            "make: ERROR [dirs.mk] | Could not find makefile `common.mk` (searched {}).\n  |\n\
+             2 | include common.mk\n  | \n\n",
+            searched("common.mk", &["missing"]),
+        ),
+        expected_files: &[],
+    },
+);
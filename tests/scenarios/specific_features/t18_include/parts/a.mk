@@ -0,0 +1,2 @@
+part-a:
+	echo a
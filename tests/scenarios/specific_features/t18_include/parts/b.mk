@@ -0,0 +1,6 @@
+ifdef GREETING
+part-b:
+	echo b
+endif
+
+PLACE = parts
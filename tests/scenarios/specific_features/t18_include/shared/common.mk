@@ -0,0 +1 @@
+COMMON := from shared
@@ -0,0 +1,13 @@
+crate::system_test_cases!({
+    args: &[],
+    expected_stdout: concat!(
+        "echo \"build/out foo.o src foo.c [src lib] [foo.c bar.h]\"\n",
+        "build/out foo.o src foo.c [src lib] [foo.c bar.h]\n",
+        "echo \". plain\"\n",
+        ". plain\n",
+        "echo \"sub x\"\n",
+        "sub x\n",
+    ),
+    expected_stderr: "",
+    expected_files: &[],
+});
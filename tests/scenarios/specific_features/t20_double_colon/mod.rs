@@ -0,0 +1,42 @@
+crate::system_test_cases!(
+    {
+        // When the target is missing, every rule runs, in definition order.
+        args: &[],
+        expected_stdout: "echo a >> out\necho b >> out\necho always >> out\n",
+        expected_stderr: "",
+        expected_files: &[("out", "a\nb\nalways\n"), ("a.txt", ""), ("b.txt", "")],
+        pre_hook: {
+            let dir = "tests/scenarios/specific_features/t20_double_colon";
+            std::fs::write(format!("{dir}/a.txt"), "").unwrap();
+            std::fs::write(format!("{dir}/b.txt"), "").unwrap();
+        },
+    },
+    {
+        // Only the rule whose own prerequisite is newer runs (plus the one without any).
+        args: &[],
+        expected_stdout: "echo b >> out\necho always >> out\n",
+        expected_stderr: "",
+        expected_files: &[("out", "old\nb\nalways\n"), ("a.txt", ""), ("b.txt", "")],
+        pre_hook: {
+            let dir = "tests/scenarios/specific_features/t20_double_colon";
+            std::fs::write(format!("{dir}/a.txt"), "").unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/out"), "old\n").unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/b.txt"), "").unwrap();
+        },
+    },
+    {
+        args: &[],
+        expected_stdout: "echo always >> out\n",
+        expected_stderr: "",
+        expected_files: &[("out", "old\nalways\n"), ("a.txt", ""), ("b.txt", "")],
+        pre_hook: {
+            let dir = "tests/scenarios/specific_features/t20_double_colon";
+            std::fs::write(format!("{dir}/a.txt"), "").unwrap();
+            std::fs::write(format!("{dir}/b.txt"), "").unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/out"), "old\n").unwrap();
+        },
+    },
+);
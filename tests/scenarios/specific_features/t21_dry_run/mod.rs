@@ -0,0 +1,24 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: concat!(
+            "compiling in.c into out.o\n",
+            "echo cc -c in.c -o out.o; false\n",
+            "cc -c in.c -o out.o\n",
+            "done\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // Echoed lines are fully expanded, with modifiers stripped, and nothing is executed.
+        args: &["-n"],
+        expected_stdout: concat!(
+            "echo \"compiling in.c into out.o\"\n",
+            "echo cc -c in.c -o out.o; false\n",
+            "echo done\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,12 @@
+crate::system_test_cases!({
+    // Lines with `+` are executed even when just printing.
+    args: &["-n"],
+    expected_stdout: concat!(
+        "echo \"not run\" > skipped.txt\n",
+        "echo \"forced\" > forced.txt\n",
+        "echo \"silent forced\"\n",
+        "silent forced\n",
+    ),
+    expected_stderr: "",
+    expected_files: &[("forced.txt", "forced\n"), ("skipped.txt", "")],
+});
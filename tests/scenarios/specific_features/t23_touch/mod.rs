@@ -0,0 +1,24 @@
+crate::system_test_cases!(
+    {
+        args: &["-t"],
+        expected_stdout: "echo \"forced\"\nforced\ntouch out.txt\n",
+        expected_stderr: "",
+        expected_files: &[],
+        post_hook: {
+            // The target is created empty, rather than by its recipe.
+            let out = "tests/scenarios/specific_features/t23_touch/out.txt";
+            assert_eq!(std::fs::read_to_string(out).unwrap(), "");
+            std::fs::remove_file(out).unwrap();
+        },
+    },
+    {
+        args: &["-t", "-n"],
+        expected_stdout: "echo \"forced\"\nforced\ntouch out.txt\n",
+        expected_stderr: "",
+        expected_files: &[],
+        post_hook: {
+            let out = "tests/scenarios/specific_features/t23_touch/out.txt";
+            assert!(!std::path::Path::new(out).exists());
+        },
+    },
+);
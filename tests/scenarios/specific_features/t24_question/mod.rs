@@ -0,0 +1,19 @@
+crate::system_test_cases!({
+    args: &[],
+    expected_stdout: concat!(
+        "../../../../target/debug/omake -q --no-print-directory -f sub.mk fresh; echo \"fresh: $?\"\n",
+        "fresh: 0\n",
+        "../../../../target/debug/omake -q --no-print-directory -f sub.mk stale; echo \"stale: $?\"\n",
+        "stale: 1\n",
+        "../../../../target/debug/omake -q --no-print-directory -f sub.mk missing; echo \"missing: $?\"\n",
+        "missing: 2\n",
+    ),
+    expected_stderr: "make: ERROR | No rule to make target 'missing'.\n",
+    expected_files: &[("in.txt", ""), ("fresh", "")],
+    pre_hook: {
+        let dir = "tests/scenarios/specific_features/t24_question";
+        std::fs::write(format!("{dir}/in.txt"), "").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(format!("{dir}/fresh"), "").unwrap();
+    },
+});
@@ -0,0 +1,5 @@
+fresh: in.txt
+	echo never
+
+stale: in.txt
+	echo never
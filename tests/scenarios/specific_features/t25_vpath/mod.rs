@@ -0,0 +1,11 @@
+crate::system_test_cases!({
+    args: &[],
+    expected_stdout: concat!(
+        "echo \"src/main.c | src/main.c include/defs.h\"\n",
+        "src/main.c | src/main.c include/defs.h\n",
+        "echo \"compile src/util.c\"\n",
+        "compile src/util.c\n",
+    ),
+    expected_stderr: "",
+    expected_files: &[],
+});
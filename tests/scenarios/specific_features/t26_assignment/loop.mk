@@ -0,0 +1,4 @@
+A = $(A) x
+
+all:
+	echo $(A)
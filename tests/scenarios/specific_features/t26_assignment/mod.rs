@@ -0,0 +1,23 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: concat!(
+            "echo \"[everyone] [] [default] [-O2 -g] [a world]\"\n",
+            "[everyone] [] [default] [-O2 -g] [a world]\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "loop.mk"],
+        expected_stdout: "",
+        expected_stderr: concat!(
+            "make: ERROR [loop.mk] | Recursive variable 'A' references itself (eventually).\n",
+            "  |\n",
+            "3 | all:\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+    },
+);
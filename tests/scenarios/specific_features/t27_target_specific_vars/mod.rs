@@ -0,0 +1,24 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: concat!(
+            "echo \"lib.o: -O2 release -DLIB\"\n",
+            "lib.o: -O2 release -DLIB\n",
+            "echo \"all: -O2 release\"\n",
+            "all: -O2 release\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["debug"],
+        expected_stdout: concat!(
+            "echo \"lib.o: -O2 -g debug -DLIB\"\n",
+            "lib.o: -O2 -g debug -DLIB\n",
+            "echo \"debug: -O2 -g debug\"\n",
+            "debug: -O2 -g debug\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
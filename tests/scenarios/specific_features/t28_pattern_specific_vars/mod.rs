@@ -0,0 +1,24 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: concat!(
+            "echo \"main.o: -O2 -DALL -Wall\"\n",
+            "main.o: -O2 -DALL -Wall\n",
+            "echo \"libfoo.o: -O2 -DALL -Wall -fPIC\"\n",
+            "libfoo.o: -O2 -DALL -Wall -fPIC\n",
+            "echo \"special.o: -O0\"\n",
+            "special.o: -O0\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["libbar.o"],
+        expected_stdout: concat!(
+            "echo \"libbar.o: -O2 -Wall -fPIC\"\n",
+            "libbar.o: -O2 -Wall -fPIC\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,11 @@
+.IGNORE:
+
+all: first second
+
+first:
+	false
+	echo first
+
+second:
+	false
+	echo second
@@ -0,0 +1,20 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "false\necho flaky\nflaky\nfalse\n",
+        expected_stderr: concat!(
+            "make: ERROR [Makefile] | Command `false` failed with code 1.\n",
+            "  |\n",
+            "9 | strict:\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "all.mk"],
+        expected_stdout: "false\necho first\nfirst\nfalse\necho second\nsecond\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,5 @@
+.SILENT:
+
+all:
+	echo first
+	echo second
@@ -0,0 +1,20 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "quiet\necho loud\nloud\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "all.mk"],
+        expected_stdout: "first\nsecond\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-n"],
+        expected_stdout: "echo quiet\necho loud\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,14 @@
+crate::system_test_cases!(
+    {
+        args: &["-s"],
+        expected_stdout: "first\nsecond\nforced\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["--silent", "-n"],
+        expected_stdout: "echo first\necho second\necho forced\nforced\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
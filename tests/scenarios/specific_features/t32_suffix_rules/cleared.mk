@@ -0,0 +1,7 @@
+.SUFFIXES:
+
+all: hello.txt
+
+# Without any known suffixes, this is a normal rule for the target `.src.txt`.
+.src.txt:
+	cp $< $@
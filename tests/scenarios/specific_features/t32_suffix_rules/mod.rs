@@ -0,0 +1,27 @@
+const DEFAULT_SUFFIXES: &str = concat!(
+    ".out .a .ln .o .c .cc .C .cpp .p .f .F .m .r .y .l .ym .yl .s .S .mod .sym .def .h .info .dvi ",
+    ".tex .texinfo .texi .txinfo .w .ch .web .sh .elc .el",
+);
+
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "cp hello.src hello.txt\n",
+        expected_stderr: "",
+        expected_files: &[("hello.txt", "hello\n")],
+    },
+    {
+        args: &["-f", "cleared.mk"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [cleared.mk] | No rule to make target 'hello.txt', required by 'all'.\n  |\n3 | all: hello.txt\n  | \n\n",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "vars.mk"],
+        expected_stdout: &format!(
+            "{DEFAULT_SUFFIXES}\n{DEFAULT_SUFFIXES} .src .txt\n{DEFAULT_SUFFIXES}\n"
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,9 @@
+# `$(.SUFFIXES)` follows the known suffixes as they are changed, while `$(SUFFIXES)` keeps the
+# defaults.
+DEFAULT := $(.SUFFIXES)
+.SUFFIXES: .src .txt
+
+all:
+	@echo $(DEFAULT)
+	@echo $(.SUFFIXES)
+	@echo $(SUFFIXES)
@@ -0,0 +1,28 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "exit 3\n",
+        expected_stderr: concat!(
+            "make: ERROR [Makefile] | Command `exit 3` failed with code 3.\n",
+            "  |\n",
+            "1 | exit:\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+        expected_status: 2,
+    },
+    {
+        args: &["killed"],
+        expected_stdout: "kill -KILL $$\n",
+        expected_stderr: concat!(
+            "make: ERROR [Makefile] | Command `kill -KILL $$` was killed by SIGKILL (signal 9).\n",
+            "  |\n",
+            "4 | killed:\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+        expected_status: 2,
+    },
+);
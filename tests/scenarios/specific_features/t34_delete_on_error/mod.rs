@@ -0,0 +1,36 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "echo partial > out.txt\nfalse\n",
+        expected_stderr: concat!(
+            "make: INFO  | Deleting file 'out.txt'.\n",
+            "make: ERROR [Makefile] | Command `false` failed with code 1.\n",
+            "  |\n",
+            "3 | out.txt:\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+        post_hook: {
+            let out = "tests/scenarios/specific_features/t34_delete_on_error/out.txt";
+            assert!(!std::path::Path::new(out).exists());
+        },
+    },
+    {
+        // Files which the failed recipe did not modify are kept.
+        args: &["-B", "existing.txt"],
+        expected_stdout: "false\n",
+        expected_stderr: concat!(
+            "make: ERROR [Makefile] | Command `false` failed with code 1.\n",
+            "  |\n",
+            "7 | existing.txt:\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+        post_hook: {
+            let existing = "tests/scenarios/specific_features/t34_delete_on_error/existing.txt";
+            assert_eq!(std::fs::read_to_string(existing).unwrap(), "existing\n");
+        },
+    },
+);
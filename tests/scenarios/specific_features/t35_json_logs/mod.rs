@@ -0,0 +1,34 @@
+crate::system_test_cases!(
+    {
+        args: &["--log-format=json", "-k"],
+        expected_stdout: "oops\n",
+        expected_stderr: concat!(
+            r#"{"level":"error","message":"Command `echo \"oops\" && false` failed with code 1.","#,
+            r#""target":"bad","file":"Makefile","line":3}"#,
+            "\n",
+            r#"{"level":"info","message":"Target 'dependent' not remade because of errors.","#,
+            r#""target":"dependent","file":null,"line":null}"#,
+            "\n",
+            r#"{"level":"info","message":"Target 'all' not remade because of errors.","#,
+            r#""target":"all","file":null,"line":null}"#,
+            "\n",
+            r#"{"level":"error","message":"Failed to make targets: bad.","#,
+            r#""target":null,"file":null,"line":null}"#,
+            "\n",
+        ),
+        expected_files: &[],
+    },
+    {
+        // Human-readable text is the default.
+        args: &[],
+        expected_stdout: "oops\n",
+        expected_stderr: concat!(
+            "make: ERROR [Makefile] | Command `echo \"oops\" && false` failed with code 1.\n",
+            "  |\n",
+            "3 | bad:\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,22 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "echo \"sub: 1\"\nsub: 1\necho \"top: 0 omake\"\ntop: 0 omake\n",
+        expected_stderr: "",
+        expected_files: &[("level", "1\n")],
+    },
+    {
+        // The sub-make is run even when just printing (the echoed command includes the absolute
+        // path of `omake`), and it handles `-n` itself.
+        args: &["-n"],
+        expected_stdout: &format!(
+            "{} --no-print-directory -f sub.mk\necho \"sub: 1\"\necho \"1\" > level\n\
+             echo \"top: 0 omake\"\n",
+            std::fs::canonicalize(env!("CARGO_BIN_EXE_omake"))
+                .unwrap()
+                .display(),
+        ),
+        expected_stderr: "",
+        expected_files: &[("level", "1\n")],
+    },
+);
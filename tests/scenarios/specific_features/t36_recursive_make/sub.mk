@@ -0,0 +1,3 @@
+sub:
+	echo "sub: $(MAKELEVEL)"
+	+@echo "$(MAKELEVEL)" > level
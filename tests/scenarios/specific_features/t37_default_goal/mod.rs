@@ -0,0 +1,29 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: concat!(
+            "echo \"first (default goal was first)\"\n",
+            "first (default goal was first)\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "override.mk"],
+        expected_stdout: "echo second\nsecond\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "reset.mk"],
+        expected_stdout: "echo second\nsecond\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "multiple.mk"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR | The `.DEFAULT_GOAL` variable contains more than one target.\n",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,4 @@
+.DEFAULT_GOAL = first second
+
+first second:
+	echo $@
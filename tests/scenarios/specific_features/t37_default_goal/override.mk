@@ -0,0 +1,7 @@
+.DEFAULT_GOAL := second
+
+first:
+	echo first
+
+second:
+	echo second
@@ -0,0 +1,8 @@
+first:
+	echo first
+
+# Resetting the default goal makes the next target the default.
+.DEFAULT_GOAL :=
+
+second:
+	echo second
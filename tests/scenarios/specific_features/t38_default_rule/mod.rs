@@ -0,0 +1,21 @@
+crate::system_test_cases!(
+    {
+        // Existing files without rules are up to date, so `.DEFAULT` is not run for them.
+        args: &[],
+        expected_stdout: "echo \"fallback for missing\"\nfallback for missing\necho all\nall\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["unknown"],
+        expected_stdout: "echo \"fallback for unknown\"\nfallback for unknown\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "none.mk"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [none.mk] | No rule to make target 'missing', required by 'all'.\n  |\n1 | all: missing\n  | \n\n",
+        expected_files: &[],
+    },
+);
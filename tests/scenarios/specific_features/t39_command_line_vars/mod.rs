@@ -0,0 +1,16 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "gcc -O2 -g release\nsub: cc\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // Overrides win over assignments in the makefile (including target-specific ones), and are
+        // passed down to sub-makes.
+        args: &["CC=clang", "all", "MODE=fast", "FLAGS=-O3 -march=native"],
+        expected_stdout: "clang -O3 -march=native fast\nsub: clang\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
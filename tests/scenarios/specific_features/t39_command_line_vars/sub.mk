@@ -0,0 +1,4 @@
+CC = cc
+
+sub:
+	@echo "sub: $(CC)"
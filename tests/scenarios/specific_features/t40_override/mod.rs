@@ -0,0 +1,20 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "gcc -g release -O2\n\
+            file, override, override, override\n\
+            environment, default, automatic, undefined\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // Assignments using `override` win over the command line (and `+=` appends to it), while
+        // others are ignored.
+        args: &["CC=clang", "CFLAGS=-Wall", "MODE=fast", "OPT=-O3"],
+        expected_stdout: "clang -Wall -g release -O2\n\
+            command line, override, override, override\n\
+            environment, default, automatic, undefined\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,16 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "Hello, world!\nExpanding all\ndone\n",
+        expected_stderr: "make: WARN  [Makefile] | Careful\n  |\n3 | $(warning Careful)\n  | \n\n",
+        expected_files: &[],
+    },
+    {
+        // `$(error ...)` stops parsing, with the context of the line where it appears.
+        args: &["FAIL=bad"],
+        expected_stdout: "Hello, world!\n",
+        expected_stderr: "make: WARN  [Makefile] | Careful\n  |\n3 | $(warning Careful)\n  | \n\n\
+            make: ERROR [Makefile] | Failed with bad\n  |\n9 | $(error Failed with $(FAIL))\n  | \n\n",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,16 @@
+crate::system_test_cases!(
+    {
+        // The working directory persists between lines, and the first line's `@` applies to all.
+        args: &[],
+        expected_stdout: "in specific_features\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // Modifiers on later lines are stripped.
+        args: &["echoed"],
+        expected_stdout: "echo one\necho two\none\ntwo\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
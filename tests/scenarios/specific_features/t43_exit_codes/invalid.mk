@@ -0,0 +1,3 @@
+all:
+	echo unreachable
+this is not a valid line
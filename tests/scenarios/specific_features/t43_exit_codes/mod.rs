@@ -0,0 +1,65 @@
+crate::system_test_cases!(
+    {
+        args: &["ok"],
+        expected_stdout: "ok\n",
+        expected_stderr: "",
+        expected_files: &[],
+        expected_status: 0,
+    },
+    {
+        args: &[],
+        expected_stdout: "",
+        expected_stderr: concat!(
+            "make: ERROR [Makefile] | Command `exit 1` failed with code 1.\n",
+            "  |\n",
+            "3 | fail:\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+        expected_status: 2,
+    },
+    {
+        // Keeping going still fails overall if any target failed.
+        args: &["-k"],
+        expected_stdout: "ok\n",
+        expected_stderr: concat!(
+            "make: ERROR [Makefile] | Command `exit 1` failed with code 1.\n",
+            "  |\n",
+            "3 | fail:\n",
+            "  | \n",
+            "\n",
+            "make: INFO  | Target 'all' not remade because of errors.\n",
+            "make: ERROR | Failed to make targets: fail.\n",
+        ),
+        expected_files: &[],
+        expected_status: 2,
+    },
+    {
+        args: &["-q", "Makefile"],
+        expected_stdout: "",
+        expected_stderr: "",
+        expected_files: &[],
+        expected_status: 0,
+    },
+    {
+        args: &["-q", "ok"],
+        expected_stdout: "",
+        expected_stderr: "",
+        expected_files: &[],
+        expected_status: 1,
+    },
+    {
+        args: &["-f", "invalid.mk"],
+        expected_stdout: "",
+        expected_stderr: concat!(
+            "make: ERROR [invalid.mk] | Invalid line type.\n",
+            "  |\n",
+            "3 | this is not a valid line\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+        expected_status: 2,
+    },
+);
@@ -0,0 +1,17 @@
+crate::system_test_cases!(
+    {
+        // Both existing targets are older than the source. Once `mid` is remade, `top` must see its
+        // new `mtime` (rather than the one from before `mid` was remade).
+        args: &[],
+        expected_stdout: "cat src.txt > mid\ncat mid > top\n",
+        expected_stderr: "",
+        expected_files: &[("src.txt", "new\n"), ("mid", "new\n"), ("top", "new\n")],
+        pre_hook: {
+            let dir = "tests/scenarios/specific_features/t44_mtime_cache";
+            std::fs::write(format!("{dir}/mid"), "old\n").unwrap();
+            std::fs::write(format!("{dir}/top"), "old\n").unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/src.txt"), "new\n").unwrap();
+        },
+    },
+);
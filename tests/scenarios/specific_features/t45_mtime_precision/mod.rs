@@ -0,0 +1,55 @@
+use std::fs::{self, File};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DIR: &str = "tests/scenarios/specific_features/t45_mtime_precision";
+
+/// Create `in.txt` and `out`, with the given modification times.
+fn create(input: SystemTime, output: SystemTime) {
+    for (file, mtime) in [("in.txt", input), ("out", output)] {
+        let path = format!("{DIR}/{file}");
+        fs::write(&path, format!("{file}\n")).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+}
+
+crate::system_test_cases!(
+    {
+        // The target is made half a second after its prerequisite, within the same second.
+        args: &[],
+        expected_stdout: "",
+        expected_stderr: "make: INFO  | Target 'out' is up to date.\n",
+        expected_files: &[("in.txt", "in.txt\n"), ("out", "out\n")],
+        pre_hook: {
+            let second = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+            create(second + Duration::from_millis(200), second + Duration::from_millis(700));
+        },
+    },
+    {
+        // With whole seconds, the `mtime`s are equal, so the target is remade to be safe.
+        args: &["--mtime-precision=seconds"],
+        expected_stdout: "cp in.txt out\n",
+        expected_stderr: "",
+        expected_files: &[("in.txt", "in.txt\n"), ("out", "in.txt\n")],
+        pre_hook: {
+            let second = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+            create(second + Duration::from_millis(200), second + Duration::from_millis(700));
+        },
+    },
+    {
+        // A prerequisite from the future is suspicious.
+        args: &[],
+        expected_stdout: "cp in.txt out\n",
+        expected_stderr:
+            "make: WARN  | File 'in.txt' has a modification time in the future (clock skew?).\n",
+        expected_files: &[("in.txt", "in.txt\n"), ("out", "in.txt\n")],
+        pre_hook: {
+            let now = SystemTime::now();
+            create(now + Duration::from_secs(3600), now);
+        },
+    },
+);
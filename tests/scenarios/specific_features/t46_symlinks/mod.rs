@@ -0,0 +1,62 @@
+use std::fs::{self, File};
+use std::os::unix::fs::symlink;
+use std::time::{Duration, UNIX_EPOCH};
+
+const DIR: &str = "tests/scenarios/specific_features/t46_symlinks";
+
+/// Create an old `header.h` (unless `broken`), a newer `out`, and a (brand new) symlink `link.h`
+/// pointing to `header.h`.
+fn create(broken: bool) {
+    let old = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut files = vec![("out", old + Duration::from_secs(10))];
+    if !broken {
+        files.push(("header.h", old));
+    }
+
+    for (file, mtime) in files {
+        let path = format!("{DIR}/{file}");
+        fs::write(&path, "old\n").unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+    symlink("header.h", format!("{DIR}/link.h")).unwrap();
+}
+
+crate::system_test_cases!(
+    {
+        // By default, only the `mtime` of the symlink's target matters.
+        args: &[],
+        expected_stdout: "",
+        expected_stderr: "make: INFO  | Target 'out' is up to date.\n",
+        expected_files: &[("out", "old\n"), ("header.h", "old\n"), ("link.h", "old\n")],
+        pre_hook: { create(false); },
+    },
+    {
+        args: &["-L"],
+        expected_stdout: "echo rebuilt > out\n",
+        expected_stderr: "",
+        expected_files: &[("out", "rebuilt\n"), ("header.h", "old\n"), ("link.h", "old\n")],
+        pre_hook: { create(false); },
+    },
+    {
+        // A broken symlink is considered missing...
+        args: &[],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [Makefile] | No rule to make target 'link.h', required by 'out'.\n  |\n1 | out: link.h\n  | \n\n",
+        expected_files: &[("out", "old\n"), ("link.h", "")],
+        expected_status: 2,
+        pre_hook: { create(true); },
+    },
+    {
+        // ...unless checking symlink times.
+        args: &["-L"],
+        expected_stdout: "echo rebuilt > out\n",
+        expected_stderr: "",
+        expected_files: &[("out", "rebuilt\n"), ("link.h", "")],
+        pre_hook: { create(true); },
+    },
+);
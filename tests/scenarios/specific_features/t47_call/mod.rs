@@ -0,0 +1,15 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "Hello, world! I am greet.\n(x,all) (y,all)\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "unclosed.mk"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [unclosed.mk] | Missing 'endef'.\n  |\n1 | define unclosed\n  | \n\n",
+        expected_files: &[],
+        expected_status: 2,
+    },
+);
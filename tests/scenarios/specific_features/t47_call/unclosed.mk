@@ -0,0 +1,5 @@
+define unclosed
+value
+
+all:
+	@echo unreachable
@@ -0,0 +1,3 @@
+define X
+value
+endef extra
@@ -0,0 +1,24 @@
+crate::system_test_cases!(
+    {
+        // Each line of a canned recipe is a separate command, with its own modifiers.
+        args: &["forced=ignored"],
+        expected_stdout: "one\ndefine inner\nnested\nendef\ntwo\n\
+            compiling all\necho done\ndone\nfirst value, overridden, a b\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // The modifiers of the line referring to a canned recipe apply to all of its lines.
+        args: &["quiet"],
+        expected_stdout: "one\ndefine inner\nnested\nendef\ntwo\ncompiling quiet\ndone\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "extraneous.mk"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [extraneous.mk] | Extraneous text after 'endef' directive.\n  |\n3 | endef extra\n  | \n\n",
+        expected_files: &[],
+        expected_status: 2,
+    },
+);
@@ -0,0 +1,2 @@
+loop = $$(eval $$(loop))
+$(eval $(loop))
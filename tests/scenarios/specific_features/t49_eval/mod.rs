@@ -0,0 +1,23 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "compile app.o\nlink app from app.o\ncompile lib.o\nlink lib from lib.o\n\
+            hello, app.o lib.o\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["in-recipe"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [Makefile] | Can't use `$(eval ...)` in a recipe.\n   |\n17 | in-recipe:\n   | \n\n",
+        expected_files: &[],
+        expected_status: 2,
+    },
+    {
+        args: &["-f", "forever.mk"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [forever.mk] | Evaluations nested too deeply (over 50).\n  |\n2 | $(eval $(loop))\n  | \n\n",
+        expected_files: &[],
+        expected_status: 2,
+    },
+);
@@ -0,0 +1,64 @@
+use std::fs::{self, File};
+use std::time::{Duration, UNIX_EPOCH};
+
+const DIR: &str = "tests/scenarios/specific_features/t50_debug";
+
+/// Create `main.o` and `prog`, each newer than its prerequisite.
+fn create_up_to_date() {
+    let old = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    for (i, file) in ["main.c", "main.o", "prog"].into_iter().enumerate() {
+        let path = format!("{DIR}/{file}");
+        if file != "main.c" {
+            fs::write(&path, "").unwrap();
+        }
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old + Duration::from_secs(i as u64))
+            .unwrap();
+    }
+}
+
+crate::system_test_cases!(
+    {
+        args: &["--debug"],
+        expected_stdout: "compile main.c\nlink\n",
+        expected_stderr: concat!(
+            "make: DEBUG | Target 'main.o' is out of date because it does not exist.\n",
+            "make: DEBUG | Target 'prog' is out of date because it does not exist.\n",
+            "make: DEBUG | Target 'all' is out of date because it is phony.\n",
+        ),
+        expected_files: &[],
+    },
+    {
+        args: &["--debug=implicit"],
+        expected_stdout: "compile main.c\nlink\n",
+        expected_stderr: concat!(
+            "make: DEBUG | No pattern rule found for target 'all'.\n",
+            "make: DEBUG | Using pattern rule '%.o: %.c' for target 'main.o', with stem 'main'.\n",
+            "make: DEBUG | No pattern rule found for target 'main.c'.\n",
+            "make: DEBUG | Target 'main.o' is out of date because it does not exist.\n",
+            "make: DEBUG | Target 'prog' is out of date because it does not exist.\n",
+            "make: DEBUG | Target 'all' is out of date because it is phony.\n",
+        ),
+        expected_files: &[],
+    },
+    {
+        args: &["--debug=verbose"],
+        expected_stdout: "",
+        expected_stderr: concat!(
+            "make: DEBUG | Considering target 'main.c'.\n",
+            "make: DEBUG | Considering target 'main.o'.\n",
+            "make: DEBUG | Prerequisite 'main.c' is older than target 'main.o'.\n",
+            "make: DEBUG | Target 'main.o' is up to date.\n",
+            "make: DEBUG | Considering target 'prog'.\n",
+            "make: DEBUG | Prerequisite 'main.o' is older than target 'prog'.\n",
+            "make: DEBUG | Target 'prog' is up to date.\n",
+            "make: DEBUG | Considering target 'all'.\n",
+            "make: DEBUG | Target 'all' is out of date because it is phony.\n",
+        ),
+        expected_files: &[("main.o", ""), ("prog", "")],
+        pre_hook: { create_up_to_date(); },
+    },
+);
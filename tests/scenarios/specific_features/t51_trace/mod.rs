@@ -0,0 +1,17 @@
+crate::system_test_cases!(
+    {
+        // Commands are traced even when they aren't echoed.
+        args: &["--trace"],
+        expected_stdout: "Makefile:4: target 'sub' => echo sub\necho sub\nsub\n\
+            Makefile:1: target 'all' => echo all\nall\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["--trace", "-n"],
+        expected_stdout: "Makefile:4: target 'sub' => echo sub\necho sub\n\
+            Makefile:1: target 'all' => echo all\necho all\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
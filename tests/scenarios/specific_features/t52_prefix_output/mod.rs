@@ -0,0 +1,32 @@
+const SMALL_STDOUT: &str = "[small] echo out; echo err >&2\n[small] out\n[small] partial\n";
+
+crate::system_test_cases!(
+    {
+        args: &["--prefix-output", "small"],
+        expected_stdout: SMALL_STDOUT,
+        expected_stderr: "[small] err\n",
+        expected_files: &[],
+    },
+    {
+        args: &["--prefix-output", "-j2", "--output-sync=target", "small"],
+        expected_stdout: "[small] echo out; echo err >&2\n[small] out\n[small] partial\n",
+        expected_stderr: "[small] err\n",
+        expected_files: &[],
+    },
+    {
+        // Reading STDOUT and STDERR concurrently means `big` can't deadlock.
+        args: &["--prefix-output"],
+        expected_stdout: &format!("{SMALL_STDOUT}[big] done\n"),
+        expected_stderr: &format!(
+            "[small] err\n{}",
+            (1..=100000).map(|n| format!("[big] {n}\n")).collect::<String>(),
+        ),
+        expected_files: &[],
+    },
+    {
+        args: &["small"],
+        expected_stdout: "echo out; echo err >&2\nout\npartial",
+        expected_stderr: "err\n",
+        expected_files: &[],
+    },
+);
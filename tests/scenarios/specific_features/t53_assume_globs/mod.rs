@@ -0,0 +1,46 @@
+// `out` is created fresh, so it is newer than the sources unless they are assumed to be new.
+const DIR: &str = "tests/scenarios/specific_features/t53_assume_globs";
+
+crate::system_test_cases!(
+    {
+        args: &["-W", "src/*.c"],
+        expected_stdout: "out\n",
+        expected_stderr: "",
+        expected_files: &[],
+        pre_hook: {
+            std::fs::write(format!("{DIR}/out"), "").unwrap();
+        },
+        post_hook: {
+            std::fs::remove_file(format!("{DIR}/out")).unwrap();
+        },
+    },
+    {
+        // Wildcards don't match a `/`.
+        args: &["-W", "*.c"],
+        expected_stdout: "",
+        expected_stderr: "make: INFO  | Target 'out' is up to date.\n",
+        expected_files: &[],
+        pre_hook: {
+            std::fs::write(format!("{DIR}/out"), "").unwrap();
+        },
+        post_hook: {
+            std::fs::remove_file(format!("{DIR}/out")).unwrap();
+        },
+    },
+    {
+        // Files which are both old and new are considered old.
+        args: &["-W", "src/*", "-o", "src/[ab].c", "-o", "src/c.h"],
+        expected_stdout: "",
+        expected_stderr: "make: INFO  | Target 'src/a.c' is up to date (old).\n\
+            make: INFO  | Target 'src/b.c' is up to date (old).\n\
+            make: INFO  | Target 'src/c.h' is up to date (old).\n\
+            make: INFO  | Target 'out' is up to date.\n",
+        expected_files: &[],
+        pre_hook: {
+            std::fs::write(format!("{DIR}/out"), "").unwrap();
+        },
+        post_hook: {
+            std::fs::remove_file(format!("{DIR}/out")).unwrap();
+        },
+    },
+);
@@ -0,0 +1,7 @@
+all: a .WAIT c
+
+a: c
+	@echo a
+
+c:
+	@echo c
@@ -0,0 +1,28 @@
+crate::system_test_cases!(
+    {
+        // `c` waits for `a`, even though `b` is done long before.
+        args: &["-j3"],
+        expected_stdout: "a b c\n",
+        expected_stderr: "",
+        expected_files: &[("log", "b\na\nc\n")],
+    },
+    {
+        args: &["-j3", "-f", "notparallel_targets.mk"],
+        expected_stdout: "a\nb\na b\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-j3", "-f", "notparallel.mk"],
+        expected_stdout: "a\nb\na b\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // Waiting for `a` would never finish, since it depends on `c`.
+        args: &["-j3", "-f", "conflict.mk"],
+        expected_stdout: "c\na\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,10 @@
+.NOTPARALLEL:
+
+all: a b
+	@echo $^
+
+a:
+	@sleep 0.2; echo a
+
+b:
+	@echo b
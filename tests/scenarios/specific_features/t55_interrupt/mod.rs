@@ -0,0 +1,15 @@
+const DIR: &str = "tests/scenarios/specific_features/t55_interrupt";
+
+crate::system_test_cases!(
+    {
+        // The partially made target is deleted, and nothing else is made.
+        args: &["after"],
+        expected_stdout: "",
+        expected_stderr: "make: INFO  | Deleting file 'out'.\nmake: ERROR | Interrupted by SIGINT.\n",
+        expected_files: &[],
+        expected_status: 130,
+        post_hook: {
+            assert!(!std::path::Path::new(&format!("{DIR}/out")).exists());
+        },
+    },
+);
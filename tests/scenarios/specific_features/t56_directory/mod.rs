@@ -0,0 +1,35 @@
+const STDERR: &str = concat!(
+    "make: INFO  | Chdir to `sub/dir`.\n",
+    "make: INFO  | Entering directory '",
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/scenarios/specific_features/t56_directory/sub/dir'\n",
+    "make: INFO  | Leaving directory '",
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/scenarios/specific_features/t56_directory/sub/dir'\n",
+    "make: INFO  | Chdir back to `",
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/scenarios/specific_features/t56_directory`.\n",
+);
+
+crate::system_test_cases!(
+    {
+        // Multiple directories compound, and relative paths (and `CURDIR`) are relative to the
+        // final one.
+        args: &["-C", "sub", "-C", "dir"],
+        expected_stdout: "dir file.txt\ncontents\n",
+        expected_stderr: STDERR,
+        expected_files: &[],
+    },
+    {
+        args: &["--directory=sub/dir", "CURDIR=ignored"],
+        expected_stdout: "ignored file.txt\ncontents\n",
+        expected_stderr: STDERR,
+        expected_files: &[],
+    },
+    {
+        args: &[],
+        expected_stdout: "top\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
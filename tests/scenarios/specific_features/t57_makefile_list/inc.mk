@@ -0,0 +1 @@
+$(info in $(MAKEFILE_LIST))
@@ -0,0 +1,21 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "Makefile\nin Makefile inc.mk\nMakefile inc.mk\nMakefile inc.mk\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "outer.mk", "MAKEFILE_LIST=ignored"],
+        expected_stdout: "ignored\nin ignored\nignored\nignored\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "outer.mk"],
+        expected_stdout: "outer.mk Makefile\nin outer.mk Makefile inc.mk\n\
+            outer.mk Makefile inc.mk\nouter.mk Makefile inc.mk\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,10 @@
+# Continuations in evaluated text are joined just like in a makefile.
+define rules
+LIST := one \
+	two
+all:
+	@echo foo [a \
+	b] more $$(LIST)
+endef
+
+$(eval $(rules))
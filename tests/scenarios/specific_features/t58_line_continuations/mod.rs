@@ -0,0 +1,16 @@
+crate::system_test_cases!(
+    {
+        // Outside of recipes, continuations collapse into a single space. In recipes, they are
+        // passed to the shell (with the leading tab removed).
+        args: &[],
+        expected_stdout: "a.c b.c c.c\nback\\\\\nb\necho first \\\nsecond\nfirst second\nxy\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "eval.mk"],
+        expected_stdout: "foo [a b] more one two\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1 @@
+include d.mk
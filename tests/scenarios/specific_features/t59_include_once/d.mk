@@ -0,0 +1,2 @@
+$(info parsing d.mk)
+COUNT += d
@@ -0,0 +1 @@
+include loop2.mk
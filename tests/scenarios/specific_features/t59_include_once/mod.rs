@@ -0,0 +1,17 @@
+crate::system_test_cases!(
+    {
+        // Both `b.mk` and `c.mk` include `d.mk` (by different paths), but it is only parsed once.
+        args: &[],
+        expected_stdout: "parsing d.mk\nMakefile b.mk d.mk c.mk d\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "loop.mk"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [loop2.mk] | Include loop: loop.mk -> loop2.mk -> loop.mk.\n  \
+            |\n1 | include loop.mk\n  | \n\n",
+        expected_files: &[],
+        expected_status: 2,
+    },
+);
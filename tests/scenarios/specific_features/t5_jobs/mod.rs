@@ -0,0 +1,29 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: concat!(
+            "for i in 1 2 3 4 5 6 7 8 9 10; do test -s log && break; sleep 0.1; done\n",
+            "echo slow >> log\n",
+            "echo fast >> log\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[("log", "slow\nfast\n")],
+    },
+    {
+        args: &["-j1"],
+        expected_stdout: concat!(
+            "for i in 1 2 3 4 5 6 7 8 9 10; do test -s log && break; sleep 0.1; done\n",
+            "echo slow >> log\n",
+            "echo fast >> log\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[("log", "slow\nfast\n")],
+    },
+    {
+        // With two jobs, `fast` should not have to wait for `slow`.
+        args: &["-j", "2"],
+        expected_stdout: "?",
+        expected_stderr: "",
+        expected_files: &[("log", "fast\nslow\n")],
+    },
+);
@@ -0,0 +1,17 @@
+# Targets append to a log, so making any of them twice would show up there. The order of `a` and
+# `b` is only known when running serially, so their log can be discarded.
+UNORDERED = log
+
+all: a b
+	@echo all >> log
+
+a: lib.o
+	@echo a >> $(UNORDERED)
+
+b: lib.o
+	@echo b >> $(UNORDERED)
+
+lib.o:
+	@echo lib.o >> log
+
+.PHONY: all a b lib.o
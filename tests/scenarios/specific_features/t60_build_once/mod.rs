@@ -0,0 +1,23 @@
+crate::system_test_cases!(
+    {
+        // Even when remaking everything, the shared prerequisites (`lib.o`, and the phony target
+        // it depends on) are only made once.
+        args: &["-B"],
+        expected_stdout: "phony\necho lib > lib.o\na\nb\n",
+        expected_stderr: "",
+        expected_files: &[("lib.o", "lib\n")],
+    },
+    {
+        // In a diamond-shaped graph, the shared prerequisite is made once.
+        args: &["-f", "diamond.mk"],
+        expected_stdout: "",
+        expected_stderr: "",
+        expected_files: &[("log", "lib.o\na\nb\nall\n")],
+    },
+    {
+        args: &["-f", "diamond.mk", "-j4", "UNORDERED=/dev/null"],
+        expected_stdout: "",
+        expected_stderr: "",
+        expected_files: &[("log", "lib.o\nall\n")],
+    },
+);
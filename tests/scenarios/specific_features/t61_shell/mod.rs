@@ -0,0 +1,32 @@
+const CONTEXT: &str = "\n  |\n1 | all:\n  | \n\n";
+
+crate::system_test_cases!(
+    {
+        args: &["SHELL=/bin/nope"],
+        expected_stdout: "",
+        expected_stderr: &format!("make: ERROR [Makefile] | SHELL '/bin/nope' not found.{CONTEXT}"),
+        expected_files: &[],
+        expected_status: 2,
+    },
+    {
+        args: &["SHELL="],
+        expected_stdout: "",
+        expected_stderr: &format!("make: ERROR [Makefile] | SHELL is empty.{CONTEXT}"),
+        expected_files: &[],
+        expected_status: 2,
+    },
+    {
+        // A shell without a directory is searched for in `PATH`.
+        args: &["SHELL=sh"],
+        expected_stdout: "hi\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // Nothing is executed when just printing, so the shell isn't needed.
+        args: &["-n", "SHELL=/bin/nope"],
+        expected_stdout: "echo hi\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,8 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "dep\ncompile y.o\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,8 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "one\ntwo\nall: one two; done\nsecond line\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,8 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "dep # is here\na#b a\\ other\n# Passed to the shell.\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1 @@
+OTHER = other
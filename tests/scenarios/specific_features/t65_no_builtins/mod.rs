@@ -0,0 +1,37 @@
+crate::system_test_cases!(
+    {
+        args: &["x.o"],
+        expected_stdout: "compile x.o\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-r", "x.o"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR | No rule to make target 'x.o'.\n",
+        expected_files: &[],
+        expected_status: 2,
+    },
+    {
+        // Builtin variables are kept with `-r`.
+        args: &["-r"],
+        expected_stdout: "CC=cc SHELL=/bin/sh SUFFIXES=\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // `SHELL` is kept with `-R`.
+        args: &["--no-builtin-variables"],
+        expected_stdout: "CC= SHELL=/bin/sh SUFFIXES=\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // `-R` implies `-r`.
+        args: &["-R", "x.o"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR | No rule to make target 'x.o'.\n",
+        expected_files: &[],
+        expected_status: 2,
+    },
+);
@@ -0,0 +1,4 @@
+# Print the data base of `Makefile` without the environment, and with the paths which depend on
+# where the tests are run replaced.
+all:
+	@env -i $(MAKE) -p -R | sed -e 's|$(MAKE)|<make>|' -e 's|$(CURDIR)|<curdir>|'
@@ -0,0 +1,58 @@
+crate::system_test_cases!(
+    {
+        // Nothing is made when printing the data base, which is printed without the environment.
+        args: &["-f", "dump.mk"],
+        expected_stdout: concat!(
+            "# Variables\n",
+            "\n",
+            "# file\n",
+            ".DEFAULT_GOAL := all\n",
+            "# default\n",
+            ".RECIPEPREFIX := \t\n",
+            "# default\n",
+            ".SHELLFLAGS := -c\n",
+            "# default\n",
+            ".SUFFIXES := \n",
+            "# file\n",
+            "CURDIR := <curdir>\n",
+            "# file\n",
+            "GREETING := hello\n",
+            "# file\n",
+            "MAKE := <make>\n",
+            "# file\n",
+            "MAKECMDGOALS := \n",
+            "# file\n",
+            "MAKEFILE_LIST := Makefile\n",
+            "# file\n",
+            "MAKEFLAGS := rR\n",
+            "# file\n",
+            "MAKELEVEL := 0\n",
+            "# file\n",
+            "NAME = made\n",
+            "# default\n",
+            "SHELL := /bin/sh\n",
+            "\n",
+            "# Default goal: all\n",
+            "\n",
+            "# Rules\n",
+            "\n",
+            "all:\n",
+            "#  Defined at Makefile:4.\n",
+            "\techo $(NAME) > $@\n",
+            "\n",
+            "# Pattern rules\n",
+            "\n",
+            "%.txt:\n",
+            "#  Defined at Makefile:7.\n",
+            "\techo $(GREETING) > $@\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[("all", "")],
+    },
+    {
+        args: &["all"],
+        expected_stdout: "echo made > all\n",
+        expected_stderr: "",
+        expected_files: &[("all", "made\n")],
+    },
+);
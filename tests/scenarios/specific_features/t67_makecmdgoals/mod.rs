@@ -0,0 +1,22 @@
+crate::system_test_cases!(
+    {
+        // The default goal is not included.
+        args: &[],
+        expected_stdout: "building []\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["clean"],
+        expected_stdout: "cleaning [clean]\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // Variable overrides are not goals.
+        args: &["all", "clean", "X=1"],
+        expected_stdout: "building [all clean]\nbuilding [all clean]\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
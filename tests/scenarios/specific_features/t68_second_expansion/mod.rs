@@ -0,0 +1,8 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "a\nb\nmain: a b\nc\ntool: c\nx.in\nx.dep\nx.extra\nx.out: x.in x.dep x.extra\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,8 @@
+# Any file can be declared intermediate, even when it has an explicit rule.
+.INTERMEDIATE: gen.txt
+
+out.txt: gen.txt
+	cp gen.txt out.txt
+
+gen.txt:
+	echo gen > gen.txt
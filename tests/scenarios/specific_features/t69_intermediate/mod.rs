@@ -0,0 +1,20 @@
+crate::system_test_cases!(
+    {
+        args: &["x.o"],
+        expected_stdout: "cp x.y x.c\ncp x.c x.o\nrm x.c\n",
+        expected_stderr: "",
+        expected_files: &[("x.o", "y\n"), ("x.c", "")],
+    },
+    {
+        args: &["-f", "secondary.mk", "x.o"],
+        expected_stdout: "cp x.y x.c\ncp x.c x.o\n",
+        expected_stderr: "",
+        expected_files: &[("x.o", "y\n"), ("x.c", "y\n")],
+    },
+    {
+        args: &["-f", "intermediate.mk"],
+        expected_stdout: "echo gen > gen.txt\ncp gen.txt out.txt\nrm gen.txt\n",
+        expected_stderr: "",
+        expected_files: &[("out.txt", "gen\n"), ("gen.txt", "")],
+    },
+);
@@ -0,0 +1,4 @@
+include Makefile
+
+# Secondary files are never deleted.
+.SECONDARY: x.c
@@ -0,0 +1,12 @@
+const OUTPUT: &str = "echo \"out a.txt a.txt b.txt a.txt b.txt\" > out
+echo 'multi1 $@'
+multi1 $@
+echo 'multi2 $@'
+multi2 $@\n";
+
+crate::system_test_cases!({
+    args: &[],
+    expected_stdout: OUTPUT,
+    expected_stderr: "",
+    expected_files: &[("out", "out a.txt a.txt b.txt a.txt b.txt\n")],
+});
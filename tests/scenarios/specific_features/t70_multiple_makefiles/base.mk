@@ -0,0 +1,8 @@
+NAME = base
+GREETING = hello
+
+all:
+	@echo $(GREETING) $(NAME)
+
+list:
+	@echo $(MAKEFILE_LIST)
@@ -0,0 +1,27 @@
+crate::system_test_cases!(
+    {
+        args: &["-f", "base.mk"],
+        expected_stdout: "hello base\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "base.mk", "-f", "overrides.mk"],
+        expected_stdout: "hello overrides\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "base.mk", "--makefile", "overrides.mk", "extra", "list"],
+        expected_stdout: "extra overrides\nbase.mk overrides.mk\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // `-` reads a makefile from stdin, in order with the others.
+        args: &["-f", "stdin.mk"],
+        expected_stdout: "hello stdin\nbase.mk -\nextra overrides\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
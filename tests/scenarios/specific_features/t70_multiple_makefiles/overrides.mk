@@ -0,0 +1,4 @@
+NAME = overrides
+
+extra:
+	@echo extra $(NAME)
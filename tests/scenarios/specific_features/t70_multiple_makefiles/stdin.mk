@@ -0,0 +1,6 @@
+OMAKE = ../../../../target/debug/omake
+
+# Feed a makefile to the sub-makes through stdin, before and after another one.
+all:
+	@printf 'NAME = stdin\n' | $(OMAKE) --no-print-directory -f base.mk -f - all list
+	@printf 'NAME = stdin\n' | $(OMAKE) --no-print-directory -f - -f overrides.mk extra
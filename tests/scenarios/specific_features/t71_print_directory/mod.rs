@@ -0,0 +1,47 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: concat!(
+            "make: INFO  | Entering directory 'DIR'\n",
+            "sub\n",
+            "make: INFO  | Leaving directory 'DIR'\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["silent"],
+        expected_stdout: "sub\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["silent-explicit"],
+        expected_stdout: concat!(
+            "make: INFO  | Entering directory 'DIR'\n",
+            "sub\n",
+            "make: INFO  | Leaving directory 'DIR'\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["disabled"],
+        expected_stdout: "sub\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // Outside of a sub-make, `-C` also prints the directory.
+        args: &["directory"],
+        expected_stdout: concat!(
+            "make: INFO  | Chdir to `sub`.\n",
+            "make: INFO  | Entering directory 'DIR/sub'\n",
+            "in sub\n",
+            "make: INFO  | Leaving directory 'DIR/sub'\n",
+            "make: INFO  | Chdir back to `DIR`.\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
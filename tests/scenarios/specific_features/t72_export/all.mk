@@ -0,0 +1,10 @@
+.EXPORT_ALL_VARIABLES:
+
+A = 1
+B = $(A)
+C = 3
+unexport C
+
+all: D = 4
+all:
+	@echo "$$A $$B [$$C] $$D"
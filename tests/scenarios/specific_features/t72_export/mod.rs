@@ -0,0 +1,32 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "hello world []\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["FROM_CLI=yes", "HIDDEN=no", "cli"],
+        expected_stdout: "yes []\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["late"],
+        expected_stdout: "hello again\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "all.mk"],
+        expected_stdout: "1 1 [] 4\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "toggle.mk"],
+        expected_stdout: "[] [] not a directive\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,9 @@
+export
+A = 1
+B = 2
+unexport
+
+export = not a directive
+
+all:
+	@echo "[$$A] [$$B] $(export)"
@@ -0,0 +1,10 @@
+crate::system_test_cases!({
+    args: &[],
+    expected_stdout: concat!(
+        "recursive simple recursive undefined\n",
+        "$(ITEMS) extra | a b | $(LIST) | \n",
+        "a b extra\n",
+    ),
+    expected_stderr: "",
+    expected_files: &[],
+});
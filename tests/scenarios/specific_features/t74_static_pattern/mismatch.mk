@@ -0,0 +1,2 @@
+a.x b.y: %.x: %.c
+	@echo never
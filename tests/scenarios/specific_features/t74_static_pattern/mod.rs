@@ -0,0 +1,44 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: concat!(
+            "mkdir build\n",
+            "foo.o from foo.c (foo) with foo.c common.h\n",
+            "bar.o from bar.c (bar) with bar.c common.h\n",
+        ),
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["other.o"],
+        expected_stdout: "pattern other.o\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-f", "mismatch.mk"],
+        expected_stdout: "",
+        expected_stderr: concat!(
+            "make: ERROR [mismatch.mk] | Target 'b.y' doesn't match the target pattern '%.x'.\n",
+            "  |\n",
+            "1 | a.x b.y: %.x: %.c\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+        expected_status: 2,
+    },
+    {
+        args: &["-f", "no_stem.mk"],
+        expected_stdout: "",
+        expected_stderr: concat!(
+            "make: ERROR [no_stem.mk] | Invalid target pattern 'a'.\n",
+            "  |\n",
+            "1 | a.o: a: a.c\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+        expected_status: 2,
+    },
+);
@@ -0,0 +1,2 @@
+a.o: a: a.c
+	@echo never
@@ -0,0 +1,31 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "-O2 [] all . a- -c\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        // Automatic variables, `$(call ...)` parameters, and special variables don't warn.
+        args: &["--warn-undefined-variables"],
+        expected_stdout: "-O2 [] all . a- -c\n",
+        expected_stderr: concat!(
+            "make: WARN  [Makefile] | Undefined variable 'CFLAG'.\n",
+            "  |\n",
+            "2 | FLAGS := $(CFLAG)\n",
+            "  | \n",
+            "\n",
+            "make: WARN  [Makefile] | Undefined variable 'PREREQS'.\n",
+            "  |\n",
+            "5 | all: $(PREREQS)\n",
+            "  | \n",
+            "\n",
+            "make: WARN  [Makefile] | Undefined variable 'CCFLAGS'.\n",
+            "  |\n",
+            "5 | all: $(PREREQS)\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+    },
+);
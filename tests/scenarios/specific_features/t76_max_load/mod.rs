@@ -0,0 +1,29 @@
+crate::system_test_cases!(
+    {
+        args: &["-j2", "-l1000"],
+        expected_stdout: "",
+        expected_stderr: "",
+        expected_files: &[("log", "fast\nslow\n")],
+    },
+    {
+        // No load average is below zero, so only one job runs at a time.
+        args: &["-j2", "--max-load=0"],
+        expected_stdout: "",
+        expected_stderr: "",
+        expected_files: &[("log", "slow\nfast\n")],
+    },
+    {
+        // Without a value, `-l` removes the limit given before it.
+        args: &["-j2", "--max-load=0", "-l"],
+        expected_stdout: "",
+        expected_stderr: "",
+        expected_files: &[("log", "fast\nslow\n")],
+    },
+    {
+        // `fast` isn't a number, so it's a target rather than the limit.
+        args: &["-j2", "-l", "fast"],
+        expected_stdout: "",
+        expected_stderr: "",
+        expected_files: &[("log", "fast\n")],
+    },
+);
@@ -0,0 +1,81 @@
+crate::system_test_cases!(
+    {
+        // The source is newer than the target, but its contents are as recorded in the manifest
+        // (along with the target's `mtime`).
+        args: &["--check-hashes"],
+        expected_stdout: "",
+        expected_stderr: "make: INFO  | Target 'out' is up to date.\n",
+        expected_files: &[("out", "new\n"), (".omake-hashes", "?")],
+        pre_hook: {
+            let dir = "tests/scenarios/specific_features/t77_check_hashes";
+            std::fs::write(format!("{dir}/out"), "new\n").unwrap();
+            let mtime = std::fs::metadata(format!("{dir}/out"))
+                .and_then(|m| m.modified())
+                .unwrap()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos();
+            std::fs::write(
+                format!("{dir}/.omake-hashes"),
+                format!("out\t{mtime}\tsrc.txt\te54954baa03a5941\n"),
+            )
+            .unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/src.txt"), "new\n").unwrap();
+        },
+    },
+    {
+        // Its contents have changed, so the target is remade.
+        args: &["--check-hashes"],
+        expected_stdout: "cat src.txt > out\n",
+        expected_stderr: "",
+        expected_files: &[("out", "new\n"), (".omake-hashes", "?")],
+        pre_hook: {
+            let dir = "tests/scenarios/specific_features/t77_check_hashes";
+            std::fs::write(format!("{dir}/out"), "old\n").unwrap();
+            std::fs::write(format!("{dir}/.omake-hashes"), "out\t0\tsrc.txt\t0000000000000000\n")
+                .unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/src.txt"), "new\n").unwrap();
+        },
+    },
+    {
+        // The target was remade without `--check-hashes` since its hashes were recorded, so they
+        // aren't trusted, even though the source's contents match them again.
+        args: &["--check-hashes"],
+        expected_stdout: "cat src.txt > out\n",
+        expected_stderr: "",
+        expected_files: &[("out", "A\n"), (".omake-hashes", "?")],
+        pre_hook: {
+            let dir = "tests/scenarios/specific_features/t77_check_hashes";
+            let omake = |args: &[&str]| {
+                std::process::Command::new("../../../../target/debug/omake")
+                    .args(args)
+                    .current_dir(dir)
+                    .output()
+                    .unwrap()
+            };
+            let _ = std::fs::remove_file(format!("{dir}/out"));
+            std::fs::write(format!("{dir}/src.txt"), "A\n").unwrap();
+            omake(&["--check-hashes"]);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/src.txt"), "B\n").unwrap();
+            omake(&[]);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/src.txt"), "A\n").unwrap();
+        },
+    },
+    {
+        // Without `--check-hashes`, only `mtime`s are compared.
+        args: &[],
+        expected_stdout: "cat src.txt > out\n",
+        expected_stderr: "",
+        expected_files: &[("out", "new\n"), (".omake-hashes", "")],
+        pre_hook: {
+            let dir = "tests/scenarios/specific_features/t77_check_hashes";
+            std::fs::write(format!("{dir}/out"), "old\n").unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/src.txt"), "new\n").unwrap();
+        },
+    },
+);
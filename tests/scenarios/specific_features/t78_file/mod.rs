@@ -0,0 +1,9 @@
+crate::system_test_cases!(
+    {
+        // Lines which expand to nothing aren't run, and the file is read after it is written.
+        args: &[],
+        expected_stdout: "cat objs.rsp\na.o b.o c.o\nd.o\nread: a.o b.o c.o d.o\n",
+        expected_stderr: "",
+        expected_files: &[("objs.rsp", "a.o b.o c.o\nd.o\n")],
+    },
+);
@@ -0,0 +1,28 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "make a\nmake c\nmake b\n",
+        expected_stderr: "",
+        expected_files: &[("a", ""), ("b", ""), ("c", "")],
+    },
+    {
+        args: &["-j2"],
+        expected_stdout: "?",
+        expected_stderr: "",
+        expected_files: &[("a", ""), ("b", ""), ("c", "")],
+    },
+    {
+        // Once the prerequisites exist, there is nothing to do.
+        args: &[],
+        expected_stdout: "",
+        expected_stderr: "",
+        expected_files: &[("a", ""), ("b", ""), ("c", "")],
+        pre_hook: {
+            let dir = "tests/scenarios/specific_features/t79_no_recipe";
+            std::fs::write(format!("{dir}/c"), "").unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/a"), "").unwrap();
+            std::fs::write(format!("{dir}/b"), "").unwrap();
+        },
+    },
+);
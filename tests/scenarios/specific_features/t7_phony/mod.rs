@@ -0,0 +1,16 @@
+// Note that files named `clean`, `build`, and `prep` exist in this directory, but since `clean` and
+// `prep` are phony, they (and anything depending on them) are always made.
+crate::system_test_cases!(
+    {
+        args: &["clean"],
+        expected_stdout: "echo cleaning\ncleaning\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["build"],
+        expected_stdout: "echo prep\nprep\necho building\nbuilding\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,14 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "first\nlast\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["-n"],
+        expected_stdout: "echo first\necho last\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
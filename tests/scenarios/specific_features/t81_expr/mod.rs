@@ -0,0 +1,15 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "count: 3, squared: 9, half: 1\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["zero"],
+        expected_stdout: "",
+        expected_stderr: "make: ERROR [Makefile] | Division by zero in 'expr' function: '3 % (3 - 3)'.\n  |\n9 | zero:\n  | \n\n",
+        expected_files: &[],
+        expected_status: 2,
+    },
+);
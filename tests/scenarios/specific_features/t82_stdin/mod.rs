@@ -0,0 +1,8 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "read ''\nread 'input'\nread 'input'\nread ''\n",
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
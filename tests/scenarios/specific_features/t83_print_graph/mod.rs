@@ -0,0 +1,27 @@
+const GRAPH: &str = concat!(
+    "digraph {\n",
+    "  \"all\" [shape=box];\n",
+    "  \"app\";\n",
+    "  \"main.o\";\n",
+    "  \"build\";\n",
+    "  \"all\" -> \"app\";\n",
+    "  \"app\" -> \"main.o\";\n",
+    "  \"app\" -> \"build\" [style=dashed];\n",
+    "}\n",
+);
+
+crate::system_test_cases!(
+    {
+        // Nothing is made.
+        args: &["--print-graph"],
+        expected_stdout: GRAPH,
+        expected_stderr: "",
+        expected_files: &[],
+    },
+    {
+        args: &["--print-graph=dot"],
+        expected_stdout: GRAPH,
+        expected_stderr: "",
+        expected_files: &[],
+    },
+);
@@ -0,0 +1,20 @@
+crate::system_test_cases!(
+    {
+        // The prerequisites of the rule with the recipe come first.
+        args: &[],
+        expected_stdout: "make c\nnew b\nmake a\nmake all from b a\n",
+        expected_stderr: concat!(
+            "make: WARN  [Makefile] | Overriding recipe for target 'b'.\n",
+            "  |\n",
+            "9 | b: c\n",
+            "  | \n",
+            "\n",
+            "make: WARN  [Makefile] | Ignoring old recipe for target 'b'.\n",
+            "  |\n",
+            "7 | b:\n",
+            "  | \n",
+            "\n",
+        ),
+        expected_files: &[],
+    },
+);
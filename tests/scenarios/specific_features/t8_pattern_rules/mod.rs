@@ -0,0 +1,15 @@
+// The first pattern rule is never used since its prerequisite doesn't exist, and `special-a.out`
+// uses the more specific rule since it has a shorter stem.
+const OUTPUT: &str = "echo \"generic plain plain.txt plain.out\"
+generic plain plain.txt plain.out
+echo \"special a special-a.txt special-a.out\"
+special a special-a.txt special-a.out
+echo \"generic sub/x sub/x.txt sub/x.out\"
+generic sub/x sub/x.txt sub/x.out\n";
+
+crate::system_test_cases!({
+    args: &[],
+    expected_stdout: OUTPUT,
+    expected_stderr: "",
+    expected_files: &[],
+});
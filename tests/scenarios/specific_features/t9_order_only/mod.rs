@@ -0,0 +1,21 @@
+crate::system_test_cases!(
+    {
+        args: &[],
+        expected_stdout: "touch stamp\necho \"in.txt | stamp\" > out\n",
+        expected_stderr: "",
+        expected_files: &[("out", "in.txt | stamp\n"), ("stamp", "")],
+    },
+    {
+        // A newer order-only prerequisite must not cause the target to be remade.
+        args: &[],
+        expected_stdout: "",
+        expected_stderr: "make: INFO  | Target 'out' is up to date.\n",
+        expected_files: &[("out", "old\n"), ("stamp", "")],
+        pre_hook: {
+            let dir = "tests/scenarios/specific_features/t9_order_only";
+            std::fs::write(format!("{dir}/out"), "old\n").unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::write(format!("{dir}/stamp"), "").unwrap();
+        },
+    },
+);
@@ -16,6 +16,8 @@ struct SystemTestCase {
     pub expected_stderr: String,
     /// Expected files that should be created, mapped to their content.
     pub expected_files: HashMap<String, String>,
+    /// Expected exit status (if any).
+    pub expected_status: Option<i32>,
 }
 
 impl SystemTestCase {
@@ -28,6 +30,7 @@ impl SystemTestCase {
         expected_stdout: &str,
         expected_stderr: &str,
         expected_files: &[(&str, &str)],
+        expected_status: Option<i32>,
     ) {
         let system_test = Self {
             // Trim leading/trailing slashes in `path`.
@@ -39,6 +42,7 @@ impl SystemTestCase {
                 .iter()
                 .map(|(f, c)| (f.to_string(), c.to_string()))
                 .collect::<HashMap<_, _>>(),
+            expected_status,
         };
         system_test.run();
     }
@@ -78,8 +82,21 @@ impl SystemTestCase {
             );
         }
 
-        // Assert filesystem expectations.
+        // Assert expected exit status (if specified).
+        if let Some(expected_status) = self.expected_status {
+            assert_eq!(
+                output.status.code(),
+                Some(expected_status),
+                "Exit status should match the expected exit status.",
+            );
+        }
+
+        // Assert filesystem expectations (unless expected is sentinel `"?"`, in which case the file
+        // is only cleaned up).
         for (filename, expected_content) in &self.expected_files {
+            if expected_content == "?" {
+                continue;
+            }
             let content =
                 fs::read_to_string(self.relative_path(filename)).unwrap_or_else(|_| "".to_string());
             assert_eq!(
@@ -114,6 +131,7 @@ macro_rules! system_test_cases {
             expected_stdout: $expected_stdout:expr,
             expected_stderr: $expected_stderr:expr,
             expected_files: $expected_files:expr
+            $(, expected_status: $expected_status:expr)?
             $(, pre_hook: $pre_hook:block)?
             $(, post_hook: $post_hook:block)?
             $(,)?
@@ -130,7 +148,12 @@ macro_rules! system_test_cases {
             $(
                 $($pre_hook)?
                 crate::SystemTestCase::execute(
-                    &path, $args, $expected_stdout, $expected_stderr, $expected_files
+                    &path,
+                    $args,
+                    $expected_stdout,
+                    $expected_stderr,
+                    $expected_files,
+                    None$(.or(Some($expected_status)))?,
                 );
                 $($post_hook)?
             )*